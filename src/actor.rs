@@ -0,0 +1,25 @@
+use axum::http::HeaderMap;
+use std::net::SocketAddr;
+
+/// Header naming the authenticated user, set by an authenticating reverse proxy
+pub const ACTOR_HEADER: &str = "x-forwarded-user";
+
+/// Request extension with the peer of the connection a request arrived on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientAddr {
+    Tcp(SocketAddr),
+    /// A process connected to the Unix domain socket
+    Unix,
+}
+
+/// The user a request was made by, if a trusted authenticating proxy named one.
+///
+/// The header of other clients is removed before requests reach the handlers.
+pub fn actor(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(ACTOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|user| !user.is_empty())
+        .map(str::to_string)
+}
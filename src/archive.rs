@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Read, Write},
+};
+use utoipa::ToSchema;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{lifecycle::civil_from_days, records::now};
+
+/// Name of the manifest inside export archives
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Suffix of flag definition files
+pub const FLAG_FILE_SUFFIX: &str = ".flagd.json";
+
+/// Largest total size of the files unpacked from an uploaded archive
+const MAX_UNPACKED_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A flag definition file to put into an archive
+pub struct ArchiveFile {
+    /// Name of the flag set
+    pub name: String,
+    pub content: Vec<u8>,
+    /// Unix timestamp in seconds of the last modification of the file
+    pub modified_at: u64,
+}
+
+/// Entry of the archive manifest describing one flag definition file
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    /// Name of the flag set
+    #[schema(example = "demo")]
+    pub name: String,
+    /// Path of the file inside the archive
+    #[schema(example = "demo.flagd.json")]
+    pub file: String,
+    /// Hex encoded SHA-256 of the file
+    pub sha256: String,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Unix timestamp in seconds of the last modification of the file
+    pub modified_at: u64,
+}
+
+/// Manifest of an export archive
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// Unix timestamp in seconds when the archive was created
+    pub created_at: u64,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl ManifestEntry {
+    pub fn new(file: &ArchiveFile) -> Self {
+        Self {
+            name: file.name.clone(),
+            file: format!("{}{}", file.name, FLAG_FILE_SUFFIX),
+            sha256: sha256_hex(&file.content),
+            size: file.content.len() as u64,
+            modified_at: file.modified_at,
+        }
+    }
+}
+
+impl Manifest {
+    pub fn new(files: &[ArchiveFile]) -> Self {
+        Self {
+            created_at: now(),
+            files: files.iter().map(ManifestEntry::new).collect(),
+        }
+    }
+}
+
+/// Hex encoded SHA-256 of some bytes
+pub fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Write a gzip compressed tar archive of the files and their manifest to `writer`,
+/// returning the writer once the archive is complete.
+///
+/// The files are taken from `files` one at a time as their entries are written, so
+/// only one of them is held in memory. The manifest is the last entry, as it is only
+/// complete once all files were read.
+pub fn write_tar_gz<W: Write>(
+    files: impl IntoIterator<Item = std::io::Result<ArchiveFile>>,
+    writer: W,
+) -> std::io::Result<W> {
+    let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+
+    let mut append = |path: &str, content: &[u8], modified_at: u64| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(modified_at);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content)
+    };
+
+    let mut entries = Vec::new();
+    for file in files {
+        let file = file?;
+        append(
+            &format!("{}{}", file.name, FLAG_FILE_SUFFIX),
+            &file.content,
+            file.modified_at,
+        )?;
+        entries.push(ManifestEntry::new(&file));
+    }
+
+    let manifest = Manifest {
+        created_at: now(),
+        files: entries,
+    };
+    append(
+        MANIFEST_FILE,
+        &manifest_json(&manifest)?,
+        manifest.created_at,
+    )?;
+
+    builder.into_inner()?.finish()
+}
+
+/// A zip archive of the files and their manifest, built in memory because zip writes
+/// its central directory by seeking back into the archive
+pub fn write_zip(files: &[ArchiveFile]) -> zip::result::ZipResult<Vec<u8>> {
+    let manifest = manifest_json(&Manifest::new(files))?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+    let mut append = |path: &str, content: &[u8], modified_at: u64| {
+        let mut options =
+            SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        if let Some(time) = zip_time(modified_at) {
+            options = options.last_modified_time(time);
+        }
+        writer.start_file(path, options)?;
+        writer.write_all(content)?;
+        Ok::<_, zip::result::ZipError>(())
+    };
+
+    append(MANIFEST_FILE, &manifest, now())?;
+    for file in files {
+        append(
+            &format!("{}{}", file.name, FLAG_FILE_SUFFIX),
+            &file.content,
+            file.modified_at,
+        )?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+fn manifest_json(manifest: &Manifest) -> std::io::Result<Vec<u8>> {
+    serde_json::to_vec_pretty(manifest).map_err(std::io::Error::other)
+}
+
+/// Zip timestamp of a Unix timestamp, zip cannot store dates before 1980
+fn zip_time(timestamp: u64) -> Option<zip::DateTime> {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days);
+
+    zip::DateTime::from_date_and_time(
+        u16::try_from(year).ok()?,
+        month as u8,
+        day as u8,
+        (seconds / 3600) as u8,
+        (seconds % 3600 / 60) as u8,
+        (seconds % 60) as u8,
+    )
+    .ok()
+}
+
+/// Flag definition files of a gzip compressed tar archive, see [`flag_files`]
+pub fn read_tar_gz(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let decoder = flate2::read::GzDecoder::new(archive);
+    let mut archive = tar::Archive::new(decoder);
+    let mut entries = Vec::new();
+    let mut remaining = MAX_UNPACKED_SIZE;
+
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(|e| e.to_string())?;
+        let path = path.to_string_lossy().into_owned();
+        entries.push((path, read_limited(entry, &mut remaining)?));
+    }
+
+    flag_files(entries)
+}
+
+/// Flag definition files of a zip archive, see [`flag_files`]
+pub fn read_zip(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut archive = ZipArchive::new(Cursor::new(archive)).map_err(|e| e.to_string())?;
+    let mut entries = Vec::new();
+    let mut remaining = MAX_UNPACKED_SIZE;
+
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        if !entry.is_file() {
+            continue;
+        }
+        let path = entry.name().to_string();
+        entries.push((path, read_limited(entry, &mut remaining)?));
+    }
+
+    flag_files(entries)
+}
+
+fn read_limited(entry: impl Read, remaining: &mut u64) -> Result<Vec<u8>, String> {
+    let mut content = Vec::new();
+    entry
+        .take(*remaining + 1)
+        .read_to_end(&mut content)
+        .map_err(|e| e.to_string())?;
+    *remaining = remaining
+        .checked_sub(content.len() as u64)
+        .ok_or_else(|| format!("Archive unpacks to more than {} bytes", MAX_UNPACKED_SIZE))?;
+    Ok(content)
+}
+
+/// Names and contents of the `*.flagd.json` files among the entries of an archive.
+///
+/// Directories within the archive are ignored, so archives of a whole flags directory
+/// can be imported as well. When the archive has a manifest, every file it lists must
+/// be present with the recorded SHA-256.
+fn flag_files(entries: Vec<(String, Vec<u8>)>) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut manifest = None;
+    let mut files = BTreeMap::new();
+
+    for (path, content) in entries {
+        let file_name = path.rsplit('/').next().unwrap_or_default();
+        if file_name == MANIFEST_FILE {
+            let parsed = serde_json::from_slice::<Manifest>(&content)
+                .map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+            manifest = Some(parsed);
+        } else if let Some(name) = file_name.strip_suffix(FLAG_FILE_SUFFIX) {
+            if files.insert(name.to_string(), content).is_some() {
+                return Err(format!("Archive contains '{}' more than once", file_name));
+            }
+        }
+    }
+
+    for entry in manifest.map(|manifest| manifest.files).unwrap_or_default() {
+        let content = files.get(&entry.name).ok_or_else(|| {
+            format!(
+                "'{}' is listed in {} but missing from the archive",
+                entry.file, MANIFEST_FILE
+            )
+        })?;
+        if sha256_hex(content) != entry.sha256 {
+            return Err(format!(
+                "'{}' does not match the SHA-256 in {}",
+                entry.file, MANIFEST_FILE
+            ));
+        }
+    }
+
+    Ok(files.into_iter().collect())
+}
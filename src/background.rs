@@ -0,0 +1,62 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+/// State of a background task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskStatus {
+    Running,
+    Failed(String),
+}
+
+/// Long-running background tasks (watchers, signal handlers) and whether they are
+/// still running, reported by the readiness probe
+#[derive(Debug, Clone, Default)]
+pub struct BackgroundTasks {
+    tasks: Arc<Mutex<BTreeMap<String, TaskStatus>>>,
+}
+
+impl BackgroundTasks {
+    /// Spawn a task that is expected to run until the process exits.
+    ///
+    /// The task is reported as failed once it returns, with its error if it returned
+    /// one, or if it panics.
+    pub fn spawn<F>(&self, name: &str, task: F)
+    where
+        F: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.set(name, TaskStatus::Running);
+
+        let handle = tokio::spawn(task);
+        let tasks = self.clone();
+        let name = name.to_string();
+
+        tokio::spawn(async move {
+            let error = match handle.await {
+                Ok(Ok(())) => "stopped unexpectedly".to_string(),
+                Ok(Err(error)) => error,
+                Err(error) => format!("panicked: {}", error),
+            };
+            tracing::error!(task = %name, error = %error, "Background task failed");
+            tasks.set(&name, TaskStatus::Failed(error));
+        });
+    }
+
+    /// Current status of all tasks by name
+    pub fn snapshot(&self) -> BTreeMap<String, TaskStatus> {
+        self.lock().clone()
+    }
+
+    fn set(&self, name: &str, status: TaskStatus) {
+        self.lock().insert(name.to_string(), status);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, TaskStatus>> {
+        // A panic while holding the lock cannot leave the map inconsistent
+        self.tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Mutex, MutexGuard};
+use utoipa::ToSchema;
+
+use crate::{
+    config::ServerConfig,
+    diff::FlagSetDiff,
+    error::AppResult,
+    records::{new_id, now, RecordStore},
+};
+
+/// Directory in FLAGS_DIR where change requests are stored, one JSON file each
+const CHANGE_REQUESTS_DIR: &str = ".change-requests";
+
+/// Serializes reviews, so that a change request is applied at most once
+static REVIEW_LOCK: Mutex<()> = Mutex::new(());
+
+/// State of a change request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeRequestStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A proposed change to a protected flag set, written once another user approves it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeRequest {
+    /// Identifier of the change request
+    #[schema(example = "0b6f8f5e-4a43-4b4e-9f0e-3f3cf0a6f0d1")]
+    pub id: String,
+    /// Flag set the change applies to
+    #[schema(example = "prod")]
+    pub flag_set: String,
+    pub status: ChangeRequestStatus,
+    /// User who proposed the change
+    #[schema(example = "alice")]
+    pub author: String,
+    /// Unix timestamp in seconds when the change was proposed
+    pub created_at: u64,
+    /// `ETag` of the flag set the change was proposed against
+    pub base_etag: String,
+    /// Flag set document the change was proposed against, absent in change requests
+    /// created before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub base: Option<Value>,
+    /// Proposed flag set document
+    #[schema(value_type = Object)]
+    pub content: Value,
+    /// Changes to the flag set at the time the change was proposed
+    pub changes: FlagSetDiff,
+    /// Schedule that applies the change once approved, instead of the approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    /// User who approved or rejected the change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "bob")]
+    pub reviewer: Option<String>,
+    /// Unix timestamp in seconds of the review
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_at: Option<u64>,
+    /// Comment of the reviewer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl ChangeRequest {
+    /// A new pending change request
+    pub fn new(
+        flag_set: &str,
+        author: String,
+        base_etag: String,
+        base: Value,
+        content: Value,
+        changes: FlagSetDiff,
+    ) -> Self {
+        Self {
+            id: new_id(),
+            flag_set: flag_set.to_string(),
+            status: ChangeRequestStatus::Pending,
+            author,
+            created_at: now(),
+            base_etag,
+            base: Some(base),
+            content,
+            changes,
+            schedule: None,
+            reviewer: None,
+            reviewed_at: None,
+            comment: None,
+        }
+    }
+
+    /// Record the review of the change request
+    pub fn review(
+        &mut self,
+        status: ChangeRequestStatus,
+        reviewer: String,
+        comment: Option<String>,
+    ) {
+        self.status = status;
+        self.reviewer = Some(reviewer);
+        self.reviewed_at = Some(now());
+        self.comment = comment;
+    }
+}
+
+/// Whether changes to a flag set need approval, because its name matches one of the
+/// `protected_flag_sets` patterns or its metadata sets `"protected": true`
+pub fn is_protected(config: &ServerConfig, name: &str, doc: &Value) -> bool {
+    let protected_by_metadata = doc
+        .pointer("/metadata/protected")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    protected_by_metadata
+        || config
+            .protected_flag_sets
+            .iter()
+            .any(|pattern| matches_pattern(pattern, name))
+}
+
+/// Match a name against a pattern in which `*` stands for any number of characters
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` in the pattern
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Change requests persisted in the flags directory
+pub struct ChangeRequestStore {
+    records: RecordStore,
+}
+
+impl ChangeRequestStore {
+    pub fn new(flags_dir: &str) -> Self {
+        Self {
+            records: RecordStore::new(flags_dir, CHANGE_REQUESTS_DIR, "Change request"),
+        }
+    }
+
+    /// Hold the review lock while checking and applying a change request
+    pub fn lock_reviews(&self) -> MutexGuard<'static, ()> {
+        // The lock guards no data, a panic while holding it leaves nothing inconsistent
+        REVIEW_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn get(&self, id: &str) -> AppResult<ChangeRequest> {
+        self.records.get(id)
+    }
+
+    /// All stored change requests, oldest first
+    pub fn list(&self) -> AppResult<Vec<ChangeRequest>> {
+        let mut requests = self.records.list::<ChangeRequest>()?;
+        requests.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(requests)
+    }
+
+    /// Store a change request, replacing the stored version with the same id
+    pub fn save(&self, request: &ChangeRequest) -> AppResult<()> {
+        self.records.save(&request.id, request)
+    }
+}
@@ -0,0 +1,73 @@
+use clap::Args;
+use serde_json::Value;
+use std::path::PathBuf;
+
+use super::{print_json, read_json_file, OutputFormat};
+use crate::diff::{diff_documents, ChangeKind, FieldChange};
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// Flag definition file on the left side
+    left: PathBuf,
+    /// Flag definition file on the right side
+    right: PathBuf,
+    /// Output format of the difference
+    #[arg(long, value_enum, default_value_t)]
+    format: OutputFormat,
+}
+
+pub fn run(args: DiffArgs) -> Result<bool, String> {
+    let left = read_json_file(&args.left)?;
+    let right = read_json_file(&args.right)?;
+
+    let diff = diff_documents(&left, &right);
+
+    match args.format {
+        OutputFormat::Json => print_json(&diff)?,
+        OutputFormat::Text if diff.is_empty() => {}
+        OutputFormat::Text => {
+            println!("--- {}", args.left.display());
+            println!("+++ {}", args.right.display());
+
+            for key in &diff.removed {
+                println!("- flag {}", key);
+            }
+            for key in &diff.added {
+                println!("+ flag {}", key);
+            }
+            for flag in &diff.modified {
+                println!("~ flag {}", flag.key);
+                print_changes(&flag.changes);
+            }
+            if !diff.document.is_empty() {
+                println!("~ flag set");
+                print_changes(&diff.document);
+            }
+        }
+    }
+
+    // Like diff(1): identical inputs succeed, differences are reported as failure
+    Ok(diff.is_empty())
+}
+
+fn print_changes(changes: &[FieldChange]) {
+    for change in changes {
+        match change.kind {
+            ChangeKind::Added => println!("    + {}: {}", change.path, render(&change.after)),
+            ChangeKind::Removed => println!("    - {}: {}", change.path, render(&change.before)),
+            ChangeKind::Modified => println!(
+                "    ~ {}: {} -> {}",
+                change.path,
+                render(&change.before),
+                render(&change.after)
+            ),
+        }
+    }
+}
+
+fn render(value: &Option<Value>) -> String {
+    value
+        .as_ref()
+        .map(Value::to_string)
+        .unwrap_or_else(|| "null".to_string())
+}
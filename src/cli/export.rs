@@ -0,0 +1,47 @@
+use clap::Args;
+use std::{fs, path::PathBuf};
+
+use crate::{
+    config::ServerConfig,
+    handlers::api::flags::{
+        get_flag_file_path, list_flag_set_names, read_flag_document, write_flag_document,
+    },
+};
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// Directory to write the flag definition files to
+    #[arg(long)]
+    out: PathBuf,
+    /// Only export the given flag sets (may be repeated)
+    #[arg(long = "name")]
+    names: Vec<String>,
+}
+
+pub fn run(args: ExportArgs, config: &ServerConfig) -> Result<bool, String> {
+    let available = list_flag_set_names(&config.flags_dir).map_err(|e| e.to_string())?;
+
+    let names = if args.names.is_empty() {
+        available
+    } else {
+        if let Some(missing) = args.names.iter().find(|name| !available.contains(name)) {
+            return Err(format!("Flag definition '{}' not found", missing));
+        }
+        args.names
+    };
+
+    fs::create_dir_all(&args.out)
+        .map_err(|e| format!("Failed to create {}: {}", args.out.display(), e))?;
+
+    for name in &names {
+        let source = get_flag_file_path(&config.flags_dir, name).map_err(|e| e.to_string())?;
+        let target = args.out.join(format!("{}.flagd.json", name));
+
+        let document = read_flag_document(&source).map_err(|e| e.to_string())?;
+        write_flag_document(&target, &document).map_err(|e| e.to_string())?;
+
+        println!("exported {} -> {}", name, target.display());
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,85 @@
+use clap::Args;
+use std::path::PathBuf;
+
+use super::read_json_file;
+use crate::{
+    config::ServerConfig,
+    handlers::api::flags::{get_flag_file_path, lint_flags, validate_flags, write_flag_document},
+    schema::SchemaRegistry,
+};
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// Flag definition file to import
+    file: PathBuf,
+    /// Name of the flag set (defaults to the file name without .flagd.json)
+    #[arg(long)]
+    name: Option<String>,
+    /// Replace an existing flag set with the same name
+    #[arg(long)]
+    overwrite: bool,
+}
+
+pub fn run(args: ImportArgs, config: &ServerConfig) -> Result<bool, String> {
+    let name = match args.name {
+        Some(name) => name,
+        None => flag_set_name(&args.file)?,
+    };
+    let target = get_flag_file_path(&config.flags_dir, &name).map_err(|e| e.to_string())?;
+
+    if target.exists() && !args.overwrite {
+        return Err(format!(
+            "Flag definition '{}' already exists, use --overwrite to replace it",
+            name
+        ));
+    }
+
+    let schemas =
+        SchemaRegistry::load(config.schema_file_path.as_deref()).map_err(|e| e.to_string())?;
+
+    let mut document = read_json_file(&args.file)?;
+    if let Some(fields) = document.as_object_mut() {
+        fields
+            .entry("$schema")
+            .or_insert_with(|| schemas.default_schema_url().into());
+    }
+
+    // Invalid documents are a check failure, not an error of the command itself
+    let report =
+        match validate_flags(&schemas, &document).and_then(|_| lint_flags(&document, false)) {
+            Ok(report) => report,
+            Err(error) => {
+                eprintln!("{}: {}", args.file.display(), error);
+                return Ok(false);
+            }
+        };
+
+    for warning in &report.warnings {
+        eprintln!("warning {}: {}", warning.pointer, warning.message);
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    write_flag_document(&target, &document).map_err(|e| e.to_string())?;
+
+    println!("imported {} -> {}", args.file.display(), target.display());
+
+    Ok(true)
+}
+
+/// Derive the flag set name from a file name like `production.flagd.json`
+fn flag_set_name(file: &std::path::Path) -> Result<String, String> {
+    let file_name = file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid file name: {}", file.display()))?;
+
+    let name = file_name
+        .strip_suffix(".flagd.json")
+        .or_else(|| file_name.strip_suffix(".json"))
+        .unwrap_or(file_name);
+
+    Ok(name.to_string())
+}
@@ -0,0 +1,174 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::{path::PathBuf, process::ExitCode};
+
+use crate::config::{ClientAuth, ConfigLayer, ConfigSources, LogFormat, ServerConfig};
+
+mod diff;
+mod export;
+mod import;
+mod validate;
+
+/// Exit code for a check that ran successfully but found problems or differences
+const EXIT_FAILURE: u8 = 1;
+/// Exit code for usage, I/O and other errors that prevented a check from running
+pub const EXIT_ERROR: u8 = 2;
+
+/// Web UI and tooling for flagd feature flag definition files
+#[derive(Debug, Parser)]
+#[command(name = "flagd-ui", version, about)]
+pub struct Cli {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Configuration overrides. Environment variables take precedence over these, and
+/// these over the configuration file.
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    /// Configuration file (TOML or YAML) [env: FLAGD_UI_CONFIG]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// HTTP server port [env: SERVER_PORT]
+    #[arg(long, global = true)]
+    port: Option<u16>,
+    /// IP address to listen on, e.g. 127.0.0.1 or [::] [env: BIND_ADDRESS]
+    #[arg(long, global = true)]
+    bind_address: Option<String>,
+    /// Unix domain socket to listen on instead of a TCP port [env: UNIX_SOCKET]
+    #[arg(long, global = true)]
+    unix_socket: Option<String>,
+    /// Seconds to wait for in-flight requests on shutdown [env: SHUTDOWN_TIMEOUT]
+    #[arg(long, global = true)]
+    shutdown_timeout: Option<u64>,
+    /// Log line format: text or json [env: LOG_FORMAT]
+    #[arg(long, global = true)]
+    log_format: Option<LogFormat>,
+    /// Directory for static files [env: STATIC_DIR]
+    #[arg(long, global = true)]
+    static_dir: Option<String>,
+    /// Directory for feature flag definition files [env: FLAGS_DIR]
+    #[arg(long, global = true)]
+    flags_dir: Option<String>,
+    /// flagd JSON schema file overriding the bundled schema [env: FLAGD_SCHEMA_FILE]
+    #[arg(long, global = true)]
+    schema_file: Option<String>,
+    /// Comma-separated name patterns of flag sets whose changes need approval [env: PROTECTED_FLAG_SETS]
+    #[arg(long, global = true, value_delimiter = ',')]
+    protected_flag_sets: Option<Vec<String>>,
+    /// Comma-separated IP addresses of proxies trusted to set X-Forwarded-User, `unix` for Unix socket clients [env: TRUSTED_PROXIES]
+    #[arg(long, global = true, value_delimiter = ',')]
+    trusted_proxies: Option<Vec<String>>,
+    /// PEM certificate chain, enables HTTPS [env: TLS_CERT_FILE]
+    #[arg(long, global = true)]
+    tls_cert_file: Option<String>,
+    /// PEM private key of the certificate [env: TLS_KEY_FILE]
+    #[arg(long, global = true)]
+    tls_key_file: Option<String>,
+    /// PEM CA certificates to verify client certificates against [env: TLS_CLIENT_CA_FILE]
+    #[arg(long, global = true)]
+    tls_client_ca_file: Option<String>,
+    /// Whether clients must present a certificate: required or optional [env: TLS_CLIENT_AUTH]
+    #[arg(long, global = true)]
+    tls_client_auth: Option<ClientAuth>,
+}
+
+impl ConfigArgs {
+    /// Configuration sources described by the command line
+    pub fn into_sources(self) -> ConfigSources {
+        ConfigSources {
+            file: self.config,
+            cli: ConfigLayer {
+                port: self.port,
+                bind_address: self.bind_address,
+                unix_socket: self.unix_socket,
+                shutdown_timeout: self.shutdown_timeout,
+                log_format: self.log_format,
+                static_dir: self.static_dir,
+                flags_dir: self.flags_dir,
+                schema_file: self.schema_file,
+                admin_token: None,
+                protected_flag_sets: self.protected_flag_sets,
+                trusted_proxies: self.trusted_proxies,
+                tls_cert_file: self.tls_cert_file,
+                tls_key_file: self.tls_key_file,
+                tls_client_ca_file: self.tls_client_ca_file,
+                tls_client_auth: self.tls_client_auth,
+            },
+        }
+    }
+}
+
+/// Available subcommands. Without a subcommand the server is started.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the web server (default)
+    Serve,
+    /// Validate flag definition files against the flagd schema and semantic rules
+    #[command(visible_alias = "lint")]
+    Validate(validate::ValidateArgs),
+    /// Export all flag definition files from FLAGS_DIR into a directory
+    Export(export::ExportArgs),
+    /// Validate a flag definition file and import it into FLAGS_DIR
+    Import(import::ImportArgs),
+    /// Show a flag-aware difference between two flag definition files
+    Diff(diff::DiffArgs),
+    /// Print the effective configuration with secrets redacted
+    Config,
+}
+
+/// Output format of the reporting subcommands
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// Machine readable JSON
+    Json,
+}
+
+impl Command {
+    /// Run an offline subcommand and map its outcome to a process exit code.
+    ///
+    /// Exit codes: 0 on success, 1 if the check found problems or differences,
+    /// 2 if the check could not be run at all.
+    pub fn run(self, config: &ServerConfig) -> ExitCode {
+        let result = match self {
+            Command::Serve => unreachable!("serve is handled by main"),
+            Command::Validate(args) => validate::run(args, config),
+            Command::Export(args) => export::run(args, config),
+            Command::Import(args) => import::run(args, config),
+            Command::Diff(args) => diff::run(args),
+            Command::Config => {
+                print!("{}", config);
+                Ok(true)
+            }
+        };
+
+        match result {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::from(EXIT_FAILURE),
+            Err(message) => {
+                eprintln!("error: {}", message);
+                ExitCode::from(EXIT_ERROR)
+            }
+        }
+    }
+}
+
+/// Read and parse a JSON file given on the command line
+fn read_json_file(path: &std::path::Path) -> Result<serde_json::Value, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Print a value as pretty JSON on stdout
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize output: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use clap::Args;
+use std::path::PathBuf;
+
+use super::{print_json, read_json_file, OutputFormat};
+use crate::{
+    config::ServerConfig,
+    handlers::api::{validate::validate_document, DocumentReport},
+    lint::LintSeverity,
+    schema::SchemaRegistry,
+};
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    /// Flag definition files to validate
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Treat lint warnings as failures
+    #[arg(long)]
+    strict: bool,
+    /// Output format of the report
+    #[arg(long, value_enum, default_value_t)]
+    format: OutputFormat,
+}
+
+pub fn run(args: ValidateArgs, config: &ServerConfig) -> Result<bool, String> {
+    let schemas =
+        SchemaRegistry::load(config.schema_file_path.as_deref()).map_err(|e| e.to_string())?;
+
+    let mut reports = Vec::new();
+    for file in &args.files {
+        let content = read_json_file(file)?;
+        reports.push(validate_document(
+            &schemas,
+            Some(file.display().to_string()),
+            &content,
+        ));
+    }
+
+    let passed =
+        |report: &DocumentReport| report.valid && (!args.strict || report.lint.warnings.is_empty());
+    let all_passed = reports.iter().all(passed);
+
+    match args.format {
+        OutputFormat::Json => print_json(&reports)?,
+        OutputFormat::Text => {
+            for report in &reports {
+                let name = report.name.as_deref().unwrap_or_default();
+                let status = if passed(report) { "ok" } else { "FAIL" };
+                println!("{}: {}", name, status);
+
+                for violation in &report.schema_errors {
+                    println!("  schema  {}: {}", violation.pointer, violation.message);
+                }
+                for issue in report.lint.errors.iter().chain(&report.lint.warnings) {
+                    let severity = match issue.severity {
+                        LintSeverity::Error => "error",
+                        LintSeverity::Warning => "warning",
+                    };
+                    println!(
+                        "  {:<7} {}: {} [{}]",
+                        severity, issue.pointer, issue.message, issue.code
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(all_passed)
+}
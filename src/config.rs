@@ -1,39 +1,411 @@
-use std::env;
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    env, fmt, fs,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-/// Server configuration loaded from environment variables
-#[derive(Debug, Clone)]
+use crate::actor::ClientAddr;
+
+/// Entry of `trusted_proxies` trusting all clients of the Unix socket
+const UNIX_CLIENTS: &str = "unix";
+
+/// A configuration value that must not show up in logs
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Access the secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"<redacted>\"")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("<redacted>")
+    }
+}
+
+/// Whether TLS clients must present a certificate signed by the client CA
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuth {
+    /// Reject connections without a valid client certificate
+    #[default]
+    Required,
+    /// Accept connections without a client certificate, but verify any that is presented
+    Optional,
+}
+
+impl FromStr for ClientAuth {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "required" => Ok(Self::Required),
+            "optional" => Ok(Self::Optional),
+            _ => Err(format!(
+                "expected 'required' or 'optional', got '{}'",
+                value
+            )),
+        }
+    }
+}
+
+/// Format of the log lines written to stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("expected 'text' or 'json', got '{}'", value)),
+        }
+    }
+}
+
+/// Server configuration, layered from environment variables, command-line arguments,
+/// a configuration file and defaults (in that order of precedence)
+#[derive(Debug, Clone, Serialize)]
 pub struct ServerConfig {
     /// HTTP server port
     pub port: u16,
+    /// IP address to listen on, e.g. `0.0.0.0`, `127.0.0.1` or `[::]`
+    pub bind_address: String,
+    /// Unix domain socket to listen on instead of `bind_address` and `port`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<String>,
+    /// Seconds to wait for in-flight requests to finish on shutdown
+    pub shutdown_timeout: u64,
+    /// Format of the log lines written to stdout
+    pub log_format: LogFormat,
     /// Directory for static files
     pub static_dir: String,
     /// Directory for feature flag definition files
     pub flags_dir: String,
-    /// Path to the flagd JSON schema file
-    pub schema_file_path: String,
+    /// Path to a flagd JSON schema file overriding the bundled schema
+    #[serde(rename = "schema_file", skip_serializing_if = "Option::is_none")]
+    pub schema_file_path: Option<String>,
+    /// Bearer token protecting the `/admin` endpoints, which are disabled without it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_token: Option<Secret>,
+    /// Name patterns (`*` matches any characters) of flag sets whose changes need approval
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub protected_flag_sets: Vec<String>,
+    /// IP addresses of authenticating proxies whose `X-Forwarded-User` header is
+    /// trusted, `unix` for clients of the Unix socket
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_proxies: Vec<String>,
+    /// PEM certificate chain; enables HTTPS together with `tls_key_file`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_cert_file: Option<String>,
+    /// PEM private key of the certificate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_key_file: Option<String>,
+    /// PEM CA certificates that client certificates are verified against (mutual TLS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_client_ca_file: Option<String>,
+    /// Whether clients must present a certificate when `tls_client_ca_file` is set
+    pub tls_client_auth: ClientAuth,
+    /// Where this configuration was loaded from, used to reload it
+    #[serde(skip)]
+    pub sources: ConfigSources,
+}
+
+/// One layer of configuration values. Unset values fall through to the next layer.
+///
+/// The field names are the keys of the configuration file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigLayer {
+    pub port: Option<u16>,
+    pub bind_address: Option<String>,
+    pub unix_socket: Option<String>,
+    pub shutdown_timeout: Option<u64>,
+    pub log_format: Option<LogFormat>,
+    pub static_dir: Option<String>,
+    pub flags_dir: Option<String>,
+    pub schema_file: Option<String>,
+    pub admin_token: Option<Secret>,
+    pub protected_flag_sets: Option<Vec<String>>,
+    pub trusted_proxies: Option<Vec<String>>,
+    pub tls_cert_file: Option<String>,
+    pub tls_key_file: Option<String>,
+    pub tls_client_ca_file: Option<String>,
+    pub tls_client_auth: Option<ClientAuth>,
+}
+
+/// Inputs of the configuration that do not change while the server runs
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSources {
+    /// Configuration file given on the command line; FLAGD_UI_CONFIG is used otherwise
+    pub file: Option<PathBuf>,
+    /// Values given as command-line arguments
+    pub cli: ConfigLayer,
+}
+
+impl ConfigLayer {
+    /// Read the layer from environment variables. Values that fail to parse are errors.
+    fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            port: parse_env_var("SERVER_PORT")?,
+            bind_address: env_var("BIND_ADDRESS"),
+            unix_socket: env_var("UNIX_SOCKET"),
+            shutdown_timeout: parse_env_var("SHUTDOWN_TIMEOUT")?,
+            log_format: parse_env_var("LOG_FORMAT")?,
+            static_dir: env_var("STATIC_DIR"),
+            flags_dir: env_var("FLAGS_DIR"),
+            schema_file: env_var("FLAGD_SCHEMA_FILE"),
+            admin_token: env_var("ADMIN_TOKEN").map(Secret),
+            protected_flag_sets: env_var("PROTECTED_FLAG_SETS").map(|value| split_list(&value)),
+            trusted_proxies: env_var("TRUSTED_PROXIES").map(|value| split_list(&value)),
+            tls_cert_file: env_var("TLS_CERT_FILE"),
+            tls_key_file: env_var("TLS_KEY_FILE"),
+            tls_client_ca_file: env_var("TLS_CLIENT_CA_FILE"),
+            tls_client_auth: parse_env_var("TLS_CLIENT_AUTH")?,
+        })
+    }
+
+    /// Read the layer from a TOML or YAML file, chosen by the file extension
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        match extension {
+            Some("toml") => toml::from_str(&content).map_err(|e| e.to_string()),
+            Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+            _ => Err("expected a .toml, .yaml or .yml file".to_string()),
+        }
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    /// Override the values of `config` with the values set in this layer
+    fn apply(&self, config: &mut ServerConfig) {
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        if let Some(bind_address) = &self.bind_address {
+            config.bind_address = bind_address.clone();
+        }
+        if let Some(unix_socket) = &self.unix_socket {
+            config.unix_socket = Some(unix_socket.clone());
+        }
+        if let Some(shutdown_timeout) = self.shutdown_timeout {
+            config.shutdown_timeout = shutdown_timeout;
+        }
+        if let Some(log_format) = self.log_format {
+            config.log_format = log_format;
+        }
+        if let Some(static_dir) = &self.static_dir {
+            config.static_dir = static_dir.clone();
+        }
+        if let Some(flags_dir) = &self.flags_dir {
+            config.flags_dir = flags_dir.clone();
+        }
+        if let Some(schema_file) = &self.schema_file {
+            config.schema_file_path = Some(schema_file.clone());
+        }
+        if let Some(admin_token) = &self.admin_token {
+            config.admin_token = Some(admin_token.clone());
+        }
+        if let Some(protected_flag_sets) = &self.protected_flag_sets {
+            config.protected_flag_sets = protected_flag_sets.clone();
+        }
+        if let Some(trusted_proxies) = &self.trusted_proxies {
+            config.trusted_proxies = trusted_proxies.clone();
+        }
+        if let Some(tls_cert_file) = &self.tls_cert_file {
+            config.tls_cert_file = Some(tls_cert_file.clone());
+        }
+        if let Some(tls_key_file) = &self.tls_key_file {
+            config.tls_key_file = Some(tls_key_file.clone());
+        }
+        if let Some(tls_client_ca_file) = &self.tls_client_ca_file {
+            config.tls_client_ca_file = Some(tls_client_ca_file.clone());
+        }
+        if let Some(tls_client_auth) = self.tls_client_auth {
+            config.tls_client_auth = tls_client_auth;
+        }
+    }
+}
+
+/// Read an environment variable, treating empty values as unset
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Split a comma-separated list, dropping empty entries
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read and parse an environment variable, treating empty values as unset
+fn parse_env_var<T>(name: &str) -> Result<Option<T>, String>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    env_var(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| format!("Invalid {} '{}': {}", name, value, e))
+        })
+        .transpose()
 }
 
 impl ServerConfig {
-    /// Load configuration from environment variables with sensible defaults
-    pub fn from_env() -> Self {
-        let port = env::var("SERVER_PORT")
-            .ok()
-            .and_then(|p| p.parse().ok())
-            .unwrap_or(3000);
+    /// Load and validate the configuration from all layers
+    pub fn load(sources: ConfigSources) -> Result<Self, String> {
+        let mut config = Self::default();
 
-        let static_dir = env::var("STATIC_DIR").unwrap_or_else(|_| "./public".to_string());
+        let file = sources
+            .file
+            .clone()
+            .or_else(|| env_var("FLAGD_UI_CONFIG").map(PathBuf::from));
+        if let Some(file) = &file {
+            ConfigLayer::from_file(file)?.apply(&mut config);
+        }
 
-        let flags_dir = env::var("FLAGS_DIR").unwrap_or_else(|_| "./flags".to_string());
+        sources.cli.apply(&mut config);
+        ConfigLayer::from_env()?.apply(&mut config);
 
-        let schema_file_path = env::var("FLAGD_SCHEMA_FILE")
-            .unwrap_or_else(|_| "./schema/flagd-schema.json".to_string());
+        config.validate()?;
+        config.sources = sources;
 
-        Self {
-            port,
-            static_dir,
-            flags_dir,
-            schema_file_path,
+        Ok(config)
+    }
+
+    /// Load the configuration again from the same sources
+    pub fn reload(&self) -> Result<Self, String> {
+        Self::load(self.sources.clone())
+    }
+
+    /// Reject configurations the server cannot start with
+    fn validate(&self) -> Result<(), String> {
+        if self.port == 0 {
+            return Err("Invalid port: must be between 1 and 65535".to_string());
+        }
+        parse_ip_addr(&self.bind_address)?;
+        if self.unix_socket.as_ref().is_some_and(String::is_empty) {
+            return Err("Invalid unix_socket: must not be empty".to_string());
         }
+        if cfg!(not(unix)) && self.unix_socket.is_some() {
+            return Err("Invalid unix_socket: not supported on this platform".to_string());
+        }
+        for proxy in &self.trusted_proxies {
+            if proxy != UNIX_CLIENTS && parse_ip_addr(proxy).is_err() {
+                return Err(format!(
+                    "Invalid trusted_proxies: '{}' is neither an IP address nor '{}'",
+                    proxy, UNIX_CLIENTS
+                ));
+            }
+        }
+        if self.static_dir.is_empty() {
+            return Err("Invalid static_dir: must not be empty".to_string());
+        }
+        if self.flags_dir.is_empty() {
+            return Err("Invalid flags_dir: must not be empty".to_string());
+        }
+        for (key, path) in [
+            ("schema_file", &self.schema_file_path),
+            ("tls_cert_file", &self.tls_cert_file),
+            ("tls_key_file", &self.tls_key_file),
+            ("tls_client_ca_file", &self.tls_client_ca_file),
+        ] {
+            if let Some(path) = path {
+                if !Path::new(path).is_file() {
+                    return Err(format!("Invalid {}: {} does not exist", key, path));
+                }
+            }
+        }
+        if self.tls_cert_file.is_some() != self.tls_key_file.is_some() {
+            return Err(
+                "Invalid TLS settings: tls_cert_file and tls_key_file must be set together"
+                    .to_string(),
+            );
+        }
+        if self.tls_client_ca_file.is_some() && self.tls_cert_file.is_none() {
+            return Err(
+                "Invalid TLS settings: tls_client_ca_file requires tls_cert_file and tls_key_file"
+                    .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// TCP address to listen on
+    pub fn listen_addr(&self) -> SocketAddr {
+        let ip = parse_ip_addr(&self.bind_address).unwrap_or(IpAddr::from([0, 0, 0, 0]));
+        SocketAddr::new(ip, self.port)
+    }
+
+    /// Whether the client is an authenticating proxy allowed to name the user
+    pub fn trusts_proxy(&self, client: ClientAddr) -> bool {
+        self.trusted_proxies.iter().any(|proxy| match client {
+            ClientAddr::Tcp(addr) => {
+                parse_ip_addr(proxy).is_ok_and(|ip| ip.to_canonical() == addr.ip().to_canonical())
+            }
+            ClientAddr::Unix => proxy == UNIX_CLIENTS,
+        })
+    }
+
+    /// How long to wait for in-flight requests on shutdown
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout)
+    }
+
+    /// The configuration as JSON with all secrets redacted, for logging
+    pub fn redacted(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+}
+
+/// Parse an IP address, allowing IPv6 addresses in brackets like `[::]`
+fn parse_ip_addr(address: &str) -> Result<IpAddr, String> {
+    let unbracketed = address
+        .strip_prefix('[')
+        .and_then(|address| address.strip_suffix(']'))
+        .unwrap_or(address);
+
+    unbracketed
+        .parse()
+        .map_err(|e| format!("Invalid bind_address '{}': {}", address, e))
+}
+
+/// Renders the configuration as TOML with all secrets redacted
+impl fmt::Display for ServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rendered = toml::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&rendered)
     }
 }
 
@@ -41,9 +413,21 @@ impl Default for ServerConfig {
     fn default() -> Self {
         Self {
             port: 3000,
+            bind_address: "0.0.0.0".to_string(),
+            unix_socket: None,
+            shutdown_timeout: 30,
+            log_format: LogFormat::default(),
             static_dir: "./public".to_string(),
             flags_dir: "./flags".to_string(),
-            schema_file_path: "./schema/flagd-schema.json".to_string(),
+            schema_file_path: None,
+            admin_token: None,
+            protected_flag_sets: Vec::new(),
+            trusted_proxies: Vec::new(),
+            tls_cert_file: None,
+            tls_key_file: None,
+            tls_client_ca_file: None,
+            tls_client_auth: ClientAuth::default(),
+            sources: ConfigSources::default(),
         }
     }
 }
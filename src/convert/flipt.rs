@@ -0,0 +1,562 @@
+use serde_json::{json, Map, Value};
+
+use super::{all_of, any_of, first_match, flag, fractional, insert_text, not, var, Conversion};
+
+/// Convert a Flipt export, with one document per namespace
+pub(super) fn convert(documents: &[Value]) -> Result<Conversion, String> {
+    let mut conversion = Conversion::default();
+
+    for document in documents {
+        let Some(flags) = document.get("flags").and_then(Value::as_array) else {
+            if document.get("segments").is_some() {
+                continue;
+            }
+            return Err("Expected a Flipt export with `flags`".to_string());
+        };
+        let namespace = match document.get("namespace") {
+            Some(Value::String(namespace)) => namespace.as_str(),
+            Some(namespace) => namespace
+                .get("key")
+                .and_then(Value::as_str)
+                .unwrap_or("default"),
+            None => "default",
+        };
+        let segments = array(document, "segments");
+
+        for source in flags {
+            let key = source
+                .get("key")
+                .and_then(Value::as_str)
+                .ok_or("Flipt flag without a key")?;
+            if conversion.flags.contains_key(key) {
+                conversion.untranslated(
+                    key,
+                    format!("namespace {}", namespace),
+                    "a flag with the same key was converted from another namespace",
+                );
+                continue;
+            }
+
+            let mut metadata = Map::new();
+            insert_text(&mut metadata, "description", source.get("description"));
+            let converted = match source.get("type").and_then(Value::as_str) {
+                Some("BOOLEAN_FLAG_TYPE") => Some(convert_boolean(
+                    key,
+                    source,
+                    segments,
+                    metadata,
+                    &mut conversion,
+                )),
+                _ => convert_variant(key, source, segments, metadata, &mut conversion),
+            };
+            if let Some(converted) = converted {
+                conversion.flags.insert(key.to_string(), converted);
+            }
+        }
+    }
+
+    Ok(conversion)
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Rules or rollouts in evaluation order
+fn ranked<'a>(value: &'a Value, key: &str) -> Vec<&'a Value> {
+    let mut items = array(value, key).iter().collect::<Vec<_>>();
+    items.sort_by_key(|item| item.get("rank").and_then(Value::as_i64));
+    items
+}
+
+/// A variant flag, whose variants serve their keys
+fn convert_variant(
+    key: &str,
+    source: &Value,
+    segments: &[Value],
+    metadata: Map<String, Value>,
+    conversion: &mut Conversion,
+) -> Option<Value> {
+    let variants = array(source, "variants")
+        .iter()
+        .filter_map(|variant| variant.get("key").and_then(Value::as_str))
+        .map(|variant| (variant.to_string(), Value::String(variant.to_string())))
+        .collect::<Map<_, _>>();
+    let Some(first) = variants.keys().next().cloned() else {
+        conversion.untranslated(
+            key,
+            "variants",
+            "the flag has no variants and was not converted",
+        );
+        return None;
+    };
+    if array(source, "variants").iter().any(|variant| {
+        variant
+            .get("attachment")
+            .is_some_and(|attachment| !attachment.is_null() && attachment != "")
+    }) {
+        conversion.untranslated(
+            key,
+            "variants",
+            "attachments are not converted, the variants serve their keys",
+        );
+    }
+
+    let default_variant = source
+        .get("default_variant")
+        .and_then(Value::as_str)
+        .or_else(|| {
+            array(source, "variants")
+                .iter()
+                .find(|variant| variant.get("default").and_then(Value::as_bool) == Some(true))
+                .and_then(|variant| variant.get("key").and_then(Value::as_str))
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            conversion.untranslated(
+                key,
+                "default_variant",
+                format!(
+                    "the flag has no default variant, flagd serves '{}' when no rule matches",
+                    first
+                ),
+            );
+            first
+        });
+
+    let mut branches = Vec::new();
+    let mut fallback = None;
+    for (index, rule) in ranked(source, "rules").into_iter().enumerate() {
+        let location = format!("rules[{}]", index);
+        let condition = match segment_condition(rule, rule.get("segment"), segments) {
+            Ok(condition) => condition,
+            Err(reason) => {
+                conversion.untranslated(key, location, reason);
+                continue;
+            }
+        };
+
+        // Percentages left over by the distributions get no variant from Flipt
+        let mut weights = array(rule, "distributions")
+            .iter()
+            .filter_map(|distribution| {
+                let variant = distribution.get("variant").and_then(Value::as_str)?;
+                let rollout = distribution.get("rollout").and_then(Value::as_f64)?;
+                Some((variant.to_string(), rollout))
+            })
+            .collect::<Vec<_>>();
+        let total = weights.iter().map(|(_, weight)| weight).sum::<f64>();
+        if total < 100.0 {
+            weights.push((default_variant.clone(), 100.0 - total));
+        }
+        let Some(result) = fractional(None, &weights) else {
+            continue;
+        };
+
+        if condition == Value::Bool(true) {
+            fallback = Some(result);
+            break;
+        }
+        branches.push((condition, result));
+    }
+
+    let enabled = source
+        .get("enabled")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    Some(flag(
+        enabled,
+        variants,
+        &default_variant,
+        first_match(branches, fallback),
+        metadata,
+    ))
+}
+
+/// A boolean flag, whose `enabled` is the value served when no rollout applies
+fn convert_boolean(
+    key: &str,
+    source: &Value,
+    segments: &[Value],
+    metadata: Map<String, Value>,
+    conversion: &mut Conversion,
+) -> Value {
+    let variant = |value: bool| if value { "on" } else { "off" };
+    let default_variant = variant(
+        source
+            .get("enabled")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+    );
+    let rollouts = ranked(source, "rollouts");
+
+    let mut branches = Vec::new();
+    let mut fallback = None;
+    for (index, rollout) in rollouts.iter().enumerate() {
+        let location = format!("rollouts[{}]", index);
+
+        if let Some(threshold) = rollout.get("threshold") {
+            let value = threshold
+                .get("value")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let percentage = threshold
+                .get("percentage")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0);
+            if index + 1 < rollouts.len() {
+                conversion.untranslated(
+                    key,
+                    location,
+                    "contexts outside the threshold are not checked against the rollouts that follow",
+                );
+            }
+            fallback = fractional(
+                None,
+                &[
+                    (variant(value).to_string(), percentage),
+                    (default_variant.to_string(), 100.0 - percentage),
+                ],
+            );
+            break;
+        }
+
+        let Some(segment) = rollout.get("segment") else {
+            continue;
+        };
+        let value = segment
+            .get("value")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        match segment_condition(segment, Some(segment), segments) {
+            Ok(Value::Bool(true)) => {
+                fallback = Some(json!(variant(value)));
+                break;
+            }
+            Ok(condition) => branches.push((condition, json!(variant(value)))),
+            Err(reason) => conversion.untranslated(key, location, reason),
+        }
+    }
+
+    let mut variants = Map::new();
+    variants.insert("on".to_string(), Value::Bool(true));
+    variants.insert("off".to_string(), Value::Bool(false));
+    flag(
+        true,
+        variants,
+        default_variant,
+        first_match(branches, fallback),
+        metadata,
+    )
+}
+
+/// Condition of the segments a rule or rollout refers to, `true` for segments that
+/// match every context
+fn segment_condition(
+    owner: &Value,
+    segment: Option<&Value>,
+    segments: &[Value],
+) -> Result<Value, String> {
+    let mut keys = match segment {
+        Some(Value::String(key)) => vec![key.as_str()],
+        Some(segment) => array(segment, "keys")
+            .iter()
+            .filter_map(Value::as_str)
+            .chain(segment.get("key").and_then(Value::as_str))
+            .collect(),
+        None => Vec::new(),
+    };
+    keys.extend(array(owner, "segments").iter().filter_map(Value::as_str));
+    if keys.is_empty() {
+        return Err("the rule has no segment".to_string());
+    }
+
+    let operator = segment
+        .and_then(|segment| segment.get("operator"))
+        .or_else(|| owner.get("segmentOperator"))
+        .or_else(|| owner.get("segment_operator"))
+        .and_then(Value::as_str);
+
+    let conditions = keys
+        .into_iter()
+        .map(|key| {
+            let segment = segments
+                .iter()
+                .find(|segment| segment.get("key").and_then(Value::as_str) == Some(key))
+                .ok_or_else(|| format!("segment '{}' is not part of the export", key))?;
+            let constraints = array(segment, "constraints")
+                .iter()
+                .map(constraint)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(match segment.get("match_type").and_then(Value::as_str) {
+                Some("ANY_MATCH_TYPE") if !constraints.is_empty() => any_of(constraints),
+                _ => all_of(constraints),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let matches_all = Value::Bool(true);
+    Ok(match operator {
+        Some("AND_SEGMENT_OPERATOR") => all_of(
+            conditions
+                .into_iter()
+                .filter(|condition| *condition != matches_all)
+                .collect(),
+        ),
+        _ if conditions.contains(&matches_all) => matches_all,
+        _ => any_of(conditions),
+    })
+}
+
+/// JsonLogic condition of a segment constraint
+fn constraint(constraint: &Value) -> Result<Value, String> {
+    let kind = constraint
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let operator = constraint
+        .get("operator")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let field = match kind {
+        "ENTITY_ID_COMPARISON_TYPE" => var("targetingKey"),
+        _ => var(constraint
+            .get("property")
+            .and_then(Value::as_str)
+            .ok_or("constraint without a property")?),
+    };
+    if kind == "DATETIME_COMPARISON_TYPE" {
+        return Err("date comparisons are not supported by flagd".to_string());
+    }
+
+    let text = constraint
+        .get("value")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let value = match kind {
+        "NUMBER_COMPARISON_TYPE" if !matches!(operator, "present" | "notpresent") => {
+            serde_json::from_str::<Value>(text)
+                .ok()
+                .filter(Value::is_number)
+                .ok_or_else(|| format!("'{}' is not a number", text))?
+        }
+        _ => Value::String(text.to_string()),
+    };
+    let list = || {
+        serde_json::from_str::<Vec<Value>>(text)
+            .map_err(|_| format!("'{}' is not a list of values", text))
+    };
+
+    Ok(match operator {
+        "eq" => json!({ "==": [field, value] }),
+        "neq" => json!({ "!=": [field, value] }),
+        "lt" => json!({ "<": [field, value] }),
+        "lte" => json!({ "<=": [field, value] }),
+        "gt" => json!({ ">": [field, value] }),
+        "gte" => json!({ ">=": [field, value] }),
+        "prefix" => json!({ "starts_with": [field, value] }),
+        "suffix" => json!({ "ends_with": [field, value] }),
+        "isoneof" => json!({ "in": [field, list()?] }),
+        "isnotoneof" => not(json!({ "in": [field, list()?] })),
+        "empty" => not(field),
+        "notempty" => json!({ "!!": [field] }),
+        "present" => json!({ "!=": [field, null] }),
+        "notpresent" => json!({ "==": [field, null] }),
+        "true" => json!({ "==": [field, true] }),
+        "false" => json!({ "==": [field, false] }),
+        operator => return Err(format!("operator '{}' is not supported by flagd", operator)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::{convert, ConvertSource};
+
+    /// An export of `flipt export`
+    const EXPORT: &str = r#"
+version: "1.2"
+namespace: default
+flags:
+  - key: banner
+    name: Banner
+    type: VARIANT_FLAG_TYPE
+    description: Color of the banner
+    enabled: true
+    variants:
+      - key: red
+        name: Red
+        default: true
+      - key: blue
+        name: Blue
+    rules:
+      - segment: staff
+        rank: 1
+        distributions:
+          - variant: blue
+            rollout: 100
+      - segment:
+          keys: [beta-users, dutch-users]
+          operator: AND_SEGMENT_OPERATOR
+        rank: 2
+        distributions:
+          - variant: blue
+            rollout: 30
+      - segment: everyone
+        rank: 3
+        distributions:
+          - variant: red
+            rollout: 50
+          - variant: blue
+            rollout: 50
+  - key: new-checkout
+    name: New checkout
+    type: BOOLEAN_FLAG_TYPE
+    enabled: false
+    rollouts:
+      - segment:
+          key: staff
+          value: true
+      - threshold:
+          percentage: 10
+          value: true
+segments:
+  - key: staff
+    name: Staff
+    match_type: ALL_MATCH_TYPE
+    constraints:
+      - type: STRING_COMPARISON_TYPE
+        property: email
+        operator: suffix
+        value: "@example.com"
+  - key: beta-users
+    name: Beta users
+    match_type: ANY_MATCH_TYPE
+    constraints:
+      - type: ENTITY_ID_COMPARISON_TYPE
+        operator: isoneof
+        value: '["alice","bob"]'
+      - type: NUMBER_COMPARISON_TYPE
+        property: age
+        operator: gte
+        value: "18"
+  - key: dutch-users
+    name: Dutch users
+    match_type: ALL_MATCH_TYPE
+    constraints:
+      - type: STRING_COMPARISON_TYPE
+        property: country
+        operator: eq
+        value: NL
+  - key: everyone
+    name: Everyone
+    match_type: ALL_MATCH_TYPE
+"#;
+
+    #[test]
+    fn converts_an_export() {
+        let conversion = convert(ConvertSource::Flipt, EXPORT, None).unwrap();
+
+        assert_eq!(
+            Value::Object(conversion.flags),
+            json!({
+                "banner": {
+                    "state": "ENABLED",
+                    "variants": { "red": "red", "blue": "blue" },
+                    "defaultVariant": "red",
+                    "targeting": { "if": [
+                        { "ends_with": [{ "var": "email" }, "@example.com"] },
+                        "blue",
+                        { "and": [
+                            { "or": [
+                                { "in": [{ "var": "targetingKey" }, ["alice", "bob"]] },
+                                { ">=": [{ "var": "age" }, 18] },
+                            ] },
+                            { "==": [{ "var": "country" }, "NL"] },
+                        ] },
+                        { "fractional": [["blue", 30], ["red", 70]] },
+                        { "fractional": [["red", 50], ["blue", 50]] },
+                    ] },
+                    "metadata": { "description": "Color of the banner" },
+                },
+                "new-checkout": {
+                    "state": "ENABLED",
+                    "variants": { "on": true, "off": false },
+                    "defaultVariant": "off",
+                    "targeting": { "if": [
+                        { "ends_with": [{ "var": "email" }, "@example.com"] },
+                        "on",
+                        { "fractional": [["on", 10], ["off", 90]] },
+                    ] },
+                },
+            })
+        );
+        assert!(conversion.untranslated.is_empty());
+    }
+
+    #[test]
+    fn reports_constraints_flagd_cannot_express() {
+        let input = json!({
+            "flags": [{
+                "key": "a",
+                "type": "VARIANT_FLAG_TYPE",
+                "enabled": true,
+                "variants": [{ "key": "x" }, { "key": "y" }],
+                "default_variant": "x",
+                "rules": [
+                    { "segment": "launch", "distributions": [{ "variant": "y", "rollout": 100 }] },
+                    { "segment": "missing", "distributions": [{ "variant": "y", "rollout": 100 }] },
+                ],
+            }],
+            "segments": [{
+                "key": "launch",
+                "constraints": [{
+                    "type": "DATETIME_COMPARISON_TYPE",
+                    "property": "signup",
+                    "operator": "gt",
+                    "value": "2025-01-01T00:00:00Z",
+                }],
+            }],
+        });
+        let conversion = super::convert(&[input]).unwrap();
+
+        let reasons = conversion
+            .untranslated
+            .iter()
+            .map(|rule| (rule.location.as_str(), rule.reason.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            [
+                ("rules[0]", "date comparisons are not supported by flagd"),
+                ("rules[1]", "segment 'missing' is not part of the export"),
+            ]
+        );
+        assert!(conversion.flags["a"].get("targeting").is_none());
+    }
+
+    #[test]
+    fn keeps_the_first_flag_of_a_key_across_namespaces() {
+        let namespace = |name: &str, variant: &str| {
+            json!({
+                "namespace": { "key": name },
+                "flags": [{
+                    "key": "a",
+                    "enabled": true,
+                    "variants": [{ "key": variant }],
+                    "default_variant": variant,
+                }],
+            })
+        };
+        let conversion =
+            super::convert(&[namespace("default", "x"), namespace("team", "y")]).unwrap();
+
+        assert_eq!(conversion.flags["a"]["defaultVariant"], "x");
+        assert_eq!(conversion.untranslated.len(), 1);
+        assert_eq!(conversion.untranslated[0].location, "namespace team");
+    }
+}
@@ -0,0 +1,628 @@
+use serde_json::{json, Map, Value};
+
+use super::{
+    all_of, any_of, bucket_by, first_match, flag, fractional, heaviest, not, var, Conversion,
+};
+
+/// Convert a GO Feature Flag configuration, a map of flag keys to flags
+pub(super) fn convert(documents: &[Value]) -> Result<Conversion, String> {
+    let mut conversion = Conversion::default();
+
+    for document in documents {
+        let flags = document
+            .as_object()
+            .ok_or("Expected a GO Feature Flag configuration mapping flag keys to flags")?;
+
+        for (key, source) in flags {
+            if conversion.flags.contains_key(key) {
+                conversion.untranslated(key, "", "the flag is defined more than once");
+                continue;
+            }
+            let converted = if source.get("variations").is_some() {
+                convert_flag(key, source, &mut conversion)
+            } else {
+                convert_legacy_flag(key, source, &mut conversion)
+            };
+            if let Some(converted) = converted {
+                conversion.flags.insert(key.clone(), converted);
+            }
+        }
+    }
+
+    Ok(conversion)
+}
+
+fn convert_flag(key: &str, source: &Value, conversion: &mut Conversion) -> Option<Value> {
+    let variants = source
+        .get("variations")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    if variants.is_empty() {
+        conversion.untranslated(
+            key,
+            "variations",
+            "the flag has no variations and was not converted",
+        );
+        return None;
+    }
+    let bucket = source
+        .get("bucketingKey")
+        .and_then(Value::as_str)
+        .map(|attribute| bucket_by(None, attribute));
+
+    for (field, reason) in [
+        (
+            "scheduledRollout",
+            "use a scheduled change of the flag set instead",
+        ),
+        ("experimentation", "experiments are not supported by flagd"),
+    ] {
+        if source.get(field).is_some_and(|value| !value.is_null()) {
+            conversion.untranslated(key, field, reason);
+        }
+    }
+
+    let mut branches = Vec::new();
+    let mut fallback = None;
+    let rules = source.get("targeting").and_then(Value::as_array);
+    for (index, rule) in rules.into_iter().flatten().enumerate() {
+        if rule.get("disable").and_then(Value::as_bool) == Some(true) {
+            continue;
+        }
+        let location = match rule.get("name").and_then(Value::as_str) {
+            Some(name) => format!("targeting[{}] ({})", index, name),
+            None => format!("targeting[{}]", index),
+        };
+
+        let condition = match rule.get("query").and_then(Value::as_str) {
+            Some(query) => match parse_query(query) {
+                Ok(condition) => Some(condition),
+                Err(reason) => {
+                    conversion.untranslated(key, location, reason);
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let result = match rule_result(rule, bucket.clone()) {
+            Ok(result) => result,
+            Err(reason) => {
+                conversion.untranslated(key, location, reason);
+                continue;
+            }
+        };
+
+        match condition {
+            Some(condition) => branches.push((condition, result)),
+            None => {
+                fallback = Some(result);
+                break;
+            }
+        }
+    }
+
+    let default_rule = source.get("defaultRule").unwrap_or(&Value::Null);
+    let first = variants.keys().next().cloned().unwrap_or_default();
+    let default_variant = match rule_result(default_rule, bucket) {
+        _ if fallback.is_some() => first,
+        Ok(Value::String(variant)) => variant,
+        Ok(split) => {
+            fallback = Some(split);
+            heaviest(&percentages(default_rule))
+                .map(str::to_string)
+                .unwrap_or(first)
+        }
+        Err(reason) => {
+            conversion.untranslated(key, "defaultRule", reason);
+            first
+        }
+    };
+
+    let mut metadata = Map::new();
+    for (name, value) in source
+        .get("metadata")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        if matches!(value, Value::String(_) | Value::Number(_) | Value::Bool(_)) {
+            metadata.insert(name.clone(), value.clone());
+        }
+    }
+
+    let enabled = source.get("disable").and_then(Value::as_bool) != Some(true);
+    Some(flag(
+        enabled,
+        variants,
+        &default_variant,
+        first_match(branches, fallback),
+        metadata,
+    ))
+}
+
+/// A flag of the format before GO Feature Flag v1, with `true`, `false` and `default`
+/// values, a `rule` and the `percentage` of matching users that get `true`
+fn convert_legacy_flag(key: &str, source: &Value, conversion: &mut Conversion) -> Option<Value> {
+    let mut variants = Map::new();
+    for name in ["true", "false", "default"] {
+        if let Some(value) = source.get(name) {
+            variants.insert(name.to_string(), value.clone());
+        }
+    }
+    if variants.len() < 3 {
+        conversion.untranslated(
+            key,
+            "",
+            "the flag needs `variations`, or `true`, `false` and `default` values, and was not converted",
+        );
+        return None;
+    }
+
+    let percentage = source
+        .get("percentage")
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0);
+    let split = fractional(
+        None,
+        &[
+            ("true".to_string(), percentage),
+            ("false".to_string(), 100.0 - percentage),
+        ],
+    );
+    let targeting = match source.get("rule").and_then(Value::as_str) {
+        Some(rule) if !rule.trim().is_empty() => match parse_query(rule) {
+            Ok(condition) => first_match(vec![(condition, split?)], None),
+            Err(reason) => {
+                conversion.untranslated(key, "rule", reason);
+                None
+            }
+        },
+        _ => split,
+    };
+
+    let enabled = source.get("disable").and_then(Value::as_bool) != Some(true);
+    Some(flag(enabled, variants, "default", targeting, Map::new()))
+}
+
+/// Variation of a rule, or a `fractional` split of its percentages
+fn rule_result(rule: &Value, bucket: Option<Value>) -> Result<Value, String> {
+    if let Some(variation) = rule.get("variation").and_then(Value::as_str) {
+        return Ok(json!(variation));
+    }
+    if rule.get("percentage").is_some() {
+        return fractional(bucket, &percentages(rule))
+            .ok_or_else(|| "the percentages are all 0".to_string());
+    }
+    if rule.get("progressiveRollout").is_some() {
+        return Err("use a progressive rollout of the flag set instead".to_string());
+    }
+    Err("the rule serves no variation".to_string())
+}
+
+fn percentages(rule: &Value) -> Vec<(String, f64)> {
+    rule.get("percentage")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .map(|(name, weight)| (name.clone(), weight.as_f64().unwrap_or(0.0)))
+        .collect()
+}
+
+/// JsonLogic condition of a rule query like `key eq "admin" and (age gt 18 or beta pr)`
+fn parse_query(query: &str) -> Result<Value, String> {
+    let tokens = tokenize(query)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+    let condition = parser.or()?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(condition),
+        Some(token) => Err(format!("unexpected {:?} in query '{}'", token, query)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Attribute, operator or keyword
+    Word(String),
+    /// `==`, `<=`, ... and punctuation
+    Symbol(String),
+    Literal(Value),
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => text.extend(chars.next()),
+                    Some(c) => text.push(c),
+                    None => return Err(format!("unterminated string in query '{}'", query)),
+                }
+            }
+            tokens.push(Token::Literal(Value::String(text)));
+        } else if c.is_ascii_digit() || c == '-' {
+            let mut number = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_digit() || matches!(c, '-' | '.'))
+            {
+                number.push(c);
+                chars.next();
+            }
+            let value = serde_json::from_str::<Value>(&number)
+                .ok()
+                .filter(Value::is_number)
+                .ok_or_else(|| format!("invalid number '{}' in query '{}'", number, query))?;
+            tokens.push(Token::Literal(value));
+        } else if c.is_alphanumeric() || matches!(c, '_' | '$') {
+            let mut word = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | '-'))
+            {
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(match word.to_lowercase().as_str() {
+                "true" => Token::Literal(Value::Bool(true)),
+                "false" => Token::Literal(Value::Bool(false)),
+                _ => Token::Word(word),
+            });
+        } else {
+            chars.next();
+            let mut symbol = c.to_string();
+            if matches!(c, '=' | '!' | '<' | '>' | '&' | '|')
+                && chars
+                    .peek()
+                    .is_some_and(|next| matches!(next, '=' | '&' | '|'))
+            {
+                symbol.extend(chars.next());
+            }
+            tokens.push(Token::Symbol(symbol));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek_keyword(&self, keywords: &[&str]) -> bool {
+        match self.tokens.get(self.position) {
+            Some(Token::Word(word)) => keywords.contains(&word.to_lowercase().as_str()),
+            Some(Token::Symbol(symbol)) => keywords.contains(&symbol.as_str()),
+            _ => false,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Symbol(found)) if found == symbol => Ok(()),
+            found => Err(format!("expected '{}' instead of {:?}", symbol, found)),
+        }
+    }
+
+    fn or(&mut self) -> Result<Value, String> {
+        let mut conditions = vec![self.and()?];
+        while self.peek_keyword(&["or", "||"]) {
+            self.position += 1;
+            conditions.push(self.and()?);
+        }
+        Ok(any_of(conditions))
+    }
+
+    fn and(&mut self) -> Result<Value, String> {
+        let mut conditions = vec![self.unary()?];
+        while self.peek_keyword(&["and", "&&"]) {
+            self.position += 1;
+            conditions.push(self.unary()?);
+        }
+        Ok(all_of(conditions))
+    }
+
+    fn unary(&mut self) -> Result<Value, String> {
+        if self.peek_keyword(&["not", "!"]) {
+            self.position += 1;
+            return Ok(not(self.unary()?));
+        }
+        if self.peek_keyword(&["("]) {
+            self.position += 1;
+            let condition = self.or()?;
+            self.expect(")")?;
+            return Ok(condition);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Value, String> {
+        let attribute = match self.next() {
+            Some(Token::Word(attribute)) => attribute,
+            found => return Err(format!("expected an attribute instead of {:?}", found)),
+        };
+        // The evaluation context key of GO Feature Flag is flagd's targeting key
+        let field = var(if attribute == "key" {
+            "targetingKey"
+        } else {
+            &attribute
+        });
+
+        let operator = match self.next() {
+            Some(Token::Word(word)) => word.to_lowercase(),
+            Some(Token::Symbol(symbol)) => symbol,
+            found => return Err(format!("expected an operator instead of {:?}", found)),
+        };
+        if operator == "pr" {
+            return Ok(json!({ "!=": [field, null] }));
+        }
+        let value = self.value()?;
+
+        let condition = match operator.as_str() {
+            "eq" | "==" => json!({ "==": [field, value] }),
+            "ne" | "!=" => json!({ "!=": [field, value] }),
+            "lt" | "<" => json!({ "<": [field, value] }),
+            "le" | "<=" => json!({ "<=": [field, value] }),
+            "gt" | ">" => json!({ ">": [field, value] }),
+            "ge" | ">=" => json!({ ">=": [field, value] }),
+            "co" => json!({ "in": [value, field] }),
+            "sw" => json!({ "starts_with": [field, value] }),
+            "ew" => json!({ "ends_with": [field, value] }),
+            "in" => json!({ "in": [field, value] }),
+            operator => return Err(format!("operator '{}' is not supported by flagd", operator)),
+        };
+        Ok(condition)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(value),
+            Some(Token::Symbol(symbol)) if symbol == "[" => {
+                let mut values = Vec::new();
+                if self.peek_keyword(&["]"]) {
+                    self.position += 1;
+                    return Ok(Value::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    match self.next() {
+                        Some(Token::Symbol(symbol)) if symbol == "," => continue,
+                        Some(Token::Symbol(symbol)) if symbol == "]" => break,
+                        found => return Err(format!("expected ',' or ']' instead of {:?}", found)),
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            found => Err(format!("expected a value instead of {:?}", found)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::{convert, ConvertSource};
+
+    /// A GO Feature Flag configuration file
+    const EXPORT: &str = r#"
+new-checkout:
+  variations:
+    enabled: true
+    disabled: false
+  bucketingKey: companyId
+  targeting:
+    - name: staff
+      query: email ew "@example.com" and not (role eq "intern")
+      variation: enabled
+    - name: legacy
+      query: version lt 2
+      disable: true
+      variation: disabled
+    - query: country in ["NL", "BE"]
+      percentage:
+        enabled: 30
+        disabled: 70
+  defaultRule:
+    percentage:
+      enabled: 10
+      disabled: 90
+  metadata:
+    description: New checkout flow
+    owner: payments
+    tags: [checkout]
+
+banner:
+  true: red
+  false: blue
+  default: blue
+  rule: key eq "alice"
+  percentage: 100
+"#;
+
+    #[test]
+    fn converts_a_configuration() {
+        let conversion = convert(ConvertSource::GoFeatureFlag, EXPORT, None).unwrap();
+        let bucket = json!({ "cat": [{ "var": "$flagd.flagKey" }, { "var": "companyId" }] });
+
+        assert_eq!(
+            Value::Object(conversion.flags),
+            json!({
+                "new-checkout": {
+                    "state": "ENABLED",
+                    "variants": { "enabled": true, "disabled": false },
+                    "defaultVariant": "disabled",
+                    "targeting": { "if": [
+                        { "and": [
+                            { "ends_with": [{ "var": "email" }, "@example.com"] },
+                            { "!": [{ "==": [{ "var": "role" }, "intern"] }] },
+                        ] },
+                        "enabled",
+                        { "in": [{ "var": "country" }, ["NL", "BE"]] },
+                        { "fractional": [bucket, ["disabled", 70], ["enabled", 30]] },
+                        { "fractional": [bucket, ["disabled", 90], ["enabled", 10]] },
+                    ] },
+                    "metadata": { "description": "New checkout flow", "owner": "payments" },
+                },
+                "banner": {
+                    "state": "ENABLED",
+                    "variants": { "true": "red", "false": "blue", "default": "blue" },
+                    "defaultVariant": "default",
+                    "targeting": { "if": [
+                        { "==": [{ "var": "targetingKey" }, "alice"] },
+                        "true",
+                    ] },
+                },
+            })
+        );
+        assert!(conversion.untranslated.is_empty());
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse_query("a eq 1 or b eq 2 and c eq 3").unwrap(),
+            json!({ "or": [
+                { "==": [{ "var": "a" }, 1] },
+                { "and": [
+                    { "==": [{ "var": "b" }, 2] },
+                    { "==": [{ "var": "c" }, 3] },
+                ] },
+            ] })
+        );
+        assert_eq!(
+            parse_query("(a eq 1 || b eq 2) && c eq 3").unwrap(),
+            json!({ "and": [
+                { "or": [
+                    { "==": [{ "var": "a" }, 1] },
+                    { "==": [{ "var": "b" }, 2] },
+                ] },
+                { "==": [{ "var": "c" }, 3] },
+            ] })
+        );
+    }
+
+    #[test]
+    fn not_applies_to_the_next_condition_only() {
+        assert_eq!(
+            parse_query("not beta pr and age ge 18").unwrap(),
+            json!({ "and": [
+                { "!": [{ "!=": [{ "var": "beta" }, null] }] },
+                { ">=": [{ "var": "age" }, 18] },
+            ] })
+        );
+        assert_eq!(
+            parse_query("! (beta pr AND age ge 18)").unwrap(),
+            json!({ "!": [{ "and": [
+                { "!=": [{ "var": "beta" }, null] },
+                { ">=": [{ "var": "age" }, 18] },
+            ] }] })
+        );
+    }
+
+    #[test]
+    fn quoted_strings_keep_keywords_and_escaped_quotes() {
+        assert_eq!(
+            parse_query(r#"name eq "Smith and \"Sons\" or (co)""#).unwrap(),
+            json!({ "==": [{ "var": "name" }, "Smith and \"Sons\" or (co)"] })
+        );
+        assert_eq!(
+            parse_query(r#"user.plan-tier co "pro""#).unwrap(),
+            json!({ "in": ["pro", { "var": "user.plan-tier" }] })
+        );
+    }
+
+    #[test]
+    fn parses_literals() {
+        assert_eq!(
+            parse_query("balance gt -10.5 and admin eq TRUE and key ne \"\"").unwrap(),
+            json!({ "and": [
+                { ">": [{ "var": "balance" }, -10.5] },
+                { "==": [{ "var": "admin" }, true] },
+                { "!=": [{ "var": "targetingKey" }, ""] },
+            ] })
+        );
+    }
+
+    #[test]
+    fn parses_in_lists() {
+        assert_eq!(
+            parse_query(r#"country in ["NL", "BE", "a,b"]"#).unwrap(),
+            json!({ "in": [{ "var": "country" }, ["NL", "BE", "a,b"]] })
+        );
+        assert_eq!(
+            parse_query("tier in [1, 2.5, true]").unwrap(),
+            json!({ "in": [{ "var": "tier" }, [1, 2.5, true]] })
+        );
+        assert_eq!(
+            parse_query("tier in []").unwrap(),
+            json!({ "in": [{ "var": "tier" }, []] })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        for query in [
+            r#"name eq "unterminated"#,
+            "country in [\"NL\" \"BE\"]",
+            "country in [\"NL\",",
+            "(a eq 1",
+            "a eq 1)",
+            "a eq 1 b eq 2",
+            "a eq",
+            "eq 1",
+            "a eq 1.2.3",
+            "",
+        ] {
+            assert!(parse_query(query).is_err(), "{} was parsed", query);
+        }
+        assert_eq!(
+            parse_query("email mt \".*\"").unwrap_err(),
+            "operator 'mt' is not supported by flagd"
+        );
+    }
+
+    #[test]
+    fn reports_rules_with_invalid_queries() {
+        let input = json!({ "a": {
+            "variations": { "on": true, "off": false },
+            "targeting": [
+                { "name": "broken", "query": "(plan eq \"pro\"", "variation": "on" },
+                { "query": "email mt \".*\"", "variation": "on" },
+                { "query": "plan eq \"pro\"", "variation": "on" },
+            ],
+            "defaultRule": { "variation": "off" },
+        } });
+        let conversion = super::convert(&[input]).unwrap();
+
+        let locations = conversion
+            .untranslated
+            .iter()
+            .map(|rule| rule.location.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(locations, ["targeting[0] (broken)", "targeting[1]"]);
+        assert_eq!(
+            conversion.flags["a"]["targeting"],
+            json!({ "if": [{ "==": [{ "var": "plan" }, "pro"] }, "on"] })
+        );
+        assert_eq!(conversion.flags["a"]["defaultVariant"], "off");
+    }
+}
@@ -0,0 +1,462 @@
+use serde_json::{json, Map, Value};
+
+use super::{
+    all_of, any_of, bucket_by, first_match, flag, fractional, heaviest, insert_text, not,
+    select_environment, unique_name, var, Conversion,
+};
+
+/// Convert the flag list of the REST API, whose flags hold their configuration per
+/// environment, or SDK flag data as used by file data sources and the Relay Proxy
+pub(super) fn convert(
+    documents: &[Value],
+    environment: Option<&str>,
+) -> Result<Conversion, String> {
+    let mut conversion = Conversion::default();
+
+    for document in documents {
+        if let Some(items) = document.get("items").and_then(Value::as_array) {
+            for item in items {
+                let key = item
+                    .get("key")
+                    .and_then(Value::as_str)
+                    .ok_or("LaunchDarkly flag without a key")?;
+                let environments = item
+                    .get("environments")
+                    .and_then(Value::as_object)
+                    .ok_or_else(|| format!("Flag '{}' has no environments", key))?;
+                let available = environments.keys().cloned().collect::<Vec<_>>();
+                let environment = select_environment(environment, &available)?
+                    .ok_or_else(|| format!("Flag '{}' has no environments", key))?;
+                let config = environments.get(environment).ok_or_else(|| {
+                    format!("Flag '{}' has no environment '{}'", key, environment)
+                })?;
+
+                let variations = item
+                    .get("variations")
+                    .and_then(Value::as_array)
+                    .map(|variations| {
+                        variations
+                            .iter()
+                            .map(|variation| {
+                                let name = variation.get("name").and_then(Value::as_str);
+                                (name, variation.get("value").cloned().unwrap_or(Value::Null))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+
+                let mut metadata = Map::new();
+                insert_text(&mut metadata, "description", item.get("description"));
+                convert_flag(key, &variations, config, metadata, &mut conversion)?;
+            }
+        } else if let Some(flags) = document.get("flags").and_then(Value::as_object) {
+            for (key, config) in flags {
+                let variations: Vec<_> = config
+                    .get("variations")
+                    .and_then(Value::as_array)
+                    .map(|values| values.iter().map(|value| (None, value.clone())).collect())
+                    .unwrap_or_default();
+                convert_flag(key, &variations, config, Map::new(), &mut conversion)?;
+            }
+        } else {
+            return Err(
+                "Expected a LaunchDarkly flag list with `items` or SDK flag data with `flags`"
+                    .to_string(),
+            );
+        }
+    }
+
+    Ok(conversion)
+}
+
+fn convert_flag(
+    key: &str,
+    variations: &[(Option<&str>, Value)],
+    config: &Value,
+    metadata: Map<String, Value>,
+    conversion: &mut Conversion,
+) -> Result<(), String> {
+    if variations.is_empty() {
+        return Err(format!("Flag '{}' has no variations", key));
+    }
+
+    let mut names = Vec::new();
+    for (index, (name, value)) in variations.iter().enumerate() {
+        let preferred = match (name, value) {
+            (Some(name), _) if !name.is_empty() => name.to_string(),
+            (_, Value::Bool(value)) => value.to_string(),
+            (_, Value::String(value)) if !value.is_empty() && value.len() <= 64 => value.clone(),
+            _ => format!("variation-{}", index),
+        };
+        names.push(unique_name(&names, preferred, index));
+    }
+    let variants = names
+        .iter()
+        .cloned()
+        .zip(variations.iter().map(|(_, value)| value.clone()))
+        .collect::<Map<_, _>>();
+
+    let prerequisites = config.get("prerequisites").and_then(Value::as_array);
+    if prerequisites.is_some_and(|prerequisites| !prerequisites.is_empty()) {
+        conversion.untranslated(
+            key,
+            "prerequisites",
+            "flagd has no prerequisites, the flag is evaluated as if they are met",
+        );
+    }
+
+    let mut branches = Vec::new();
+    for field in ["targets", "contextTargets"] {
+        let targets = config.get(field).and_then(Value::as_array);
+        for target in targets.into_iter().flatten() {
+            let values = target.get("values").and_then(Value::as_array);
+            // Context targets of users without values refer to the user targets
+            let Some(values) = values.filter(|values| !values.is_empty()) else {
+                continue;
+            };
+            let kind = target.get("contextKind").and_then(Value::as_str);
+            match variation(&names, target) {
+                Ok(result) => branches.push((
+                    json!({ "in": [var(&attribute(kind, "key")), values] }),
+                    result,
+                )),
+                Err(reason) => conversion.untranslated(key, field, reason),
+            }
+        }
+    }
+
+    let rules = config.get("rules").and_then(Value::as_array);
+    for (index, rule) in rules.into_iter().flatten().enumerate() {
+        let location = match rule.get("description").and_then(Value::as_str) {
+            Some(description) if !description.is_empty() => {
+                format!("rules[{}] ({})", index, description)
+            }
+            _ => format!("rules[{}]", index),
+        };
+        let clauses = rule
+            .get("clauses")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(clause)
+            .collect::<Result<Vec<_>, _>>();
+        match clauses.and_then(|clauses| Ok((all_of(clauses), rollout_or_variation(&names, rule)?)))
+        {
+            Ok(branch) => branches.push(branch),
+            Err(reason) => conversion.untranslated(key, location, reason),
+        }
+    }
+
+    let fallthrough = config.get("fallthrough").unwrap_or(&Value::Null);
+    let (default_variant, fallback) = match rollout_or_variation(&names, fallthrough) {
+        Ok(Value::String(variant)) => (variant, None),
+        Ok(split) => (heaviest_variant(&names, fallthrough), Some(split)),
+        Err(reason) => {
+            conversion.untranslated(key, "fallthrough", reason);
+            (names[0].clone(), None)
+        }
+    };
+
+    let enabled = config.get("on").and_then(Value::as_bool).unwrap_or(true);
+    if !enabled {
+        if let Some(off) = config.get("offVariation").and_then(Value::as_u64) {
+            conversion.untranslated(
+                key,
+                "offVariation",
+                format!(
+                    "the flag is off, LaunchDarkly serves variation '{}' while flagd makes clients use their default value",
+                    names.get(off as usize).map(String::as_str).unwrap_or_default()
+                ),
+            );
+        }
+    }
+
+    conversion.flags.insert(
+        key.to_string(),
+        flag(
+            enabled,
+            variants,
+            &default_variant,
+            first_match(branches, fallback),
+            metadata,
+        ),
+    );
+    Ok(())
+}
+
+/// Flag evaluation context attribute of a context kind's attribute
+fn attribute(kind: Option<&str>, name: &str) -> String {
+    match (kind, name) {
+        (None | Some("user"), "key") => "targetingKey".to_string(),
+        (None | Some("user"), name) => name.to_string(),
+        (Some(kind), name) => format!("{}.{}", kind, name),
+    }
+}
+
+fn variation(names: &[String], value: &Value) -> Result<Value, String> {
+    variant_name(names, value).map(Value::String)
+}
+
+fn variant_name(names: &[String], value: &Value) -> Result<String, String> {
+    let index = value
+        .get("variation")
+        .and_then(Value::as_u64)
+        .ok_or("no variation to serve")?;
+    names
+        .get(index as usize)
+        .cloned()
+        .ok_or_else(|| format!("variation {} does not exist", index))
+}
+
+/// Variant of a rule or fallthrough, or a `fractional` split of its percentage rollout
+fn rollout_or_variation(names: &[String], value: &Value) -> Result<Value, String> {
+    let Some(rollout) = value.get("rollout") else {
+        return variation(names, value);
+    };
+    if rollout.get("experimentAllocation").is_some() {
+        return Err("experiments are not supported by flagd".to_string());
+    }
+
+    let weights = rollout_weights(names, rollout)?;
+    let kind = rollout.get("contextKind").and_then(Value::as_str);
+    let bucket = match rollout.get("bucketBy").and_then(Value::as_str) {
+        None | Some("key") if matches!(kind, None | Some("user")) => None,
+        bucket => Some(bucket_by(None, &attribute(kind, bucket.unwrap_or("key")))),
+    };
+
+    fractional(bucket, &weights).ok_or_else(|| "the rollout has no weights".to_string())
+}
+
+fn rollout_weights(names: &[String], rollout: &Value) -> Result<Vec<(String, f64)>, String> {
+    rollout
+        .get("variations")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|bucket| {
+            let name = variant_name(names, bucket)?;
+            let weight = bucket.get("weight").and_then(Value::as_f64).unwrap_or(0.0);
+            Ok((name, weight))
+        })
+        .collect()
+}
+
+fn heaviest_variant(names: &[String], fallthrough: &Value) -> String {
+    fallthrough
+        .get("rollout")
+        .and_then(|rollout| rollout_weights(names, rollout).ok())
+        .and_then(|weights| heaviest(&weights).map(str::to_string))
+        .unwrap_or_else(|| names[0].clone())
+}
+
+/// JsonLogic condition of a rule clause
+fn clause(clause: &Value) -> Result<Value, String> {
+    let op = clause.get("op").and_then(Value::as_str).unwrap_or_default();
+    let kind = clause.get("contextKind").and_then(Value::as_str);
+    let name = clause
+        .get("attribute")
+        .and_then(Value::as_str)
+        .ok_or("clause without an attribute")?;
+    let attribute = var(&attribute(kind, name));
+    let values = clause
+        .get("values")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let compare = |operator: &str| {
+        values
+            .iter()
+            .map(|value| json!({ operator: [attribute, value] }))
+            .collect::<Vec<_>>()
+    };
+    let sem_ver = |operator: &str| {
+        values
+            .iter()
+            .map(|value| json!({ "sem_ver": [attribute, operator, value] }))
+            .collect::<Vec<_>>()
+    };
+
+    let conditions = match op {
+        "in" => vec![json!({ "in": [attribute, values] })],
+        "startsWith" => compare("starts_with"),
+        "endsWith" => compare("ends_with"),
+        "contains" => values
+            .iter()
+            .map(|value| json!({ "in": [value, attribute] }))
+            .collect(),
+        "lessThan" => compare("<"),
+        "lessThanOrEqual" => compare("<="),
+        "greaterThan" => compare(">"),
+        "greaterThanOrEqual" => compare(">="),
+        "semVerEqual" => sem_ver("="),
+        "semVerLessThan" => sem_ver("<"),
+        "semVerGreaterThan" => sem_ver(">"),
+        "segmentMatch" => return Err("segments are not translated".to_string()),
+        op => return Err(format!("operator '{}' is not supported by flagd", op)),
+    };
+
+    let condition = any_of(conditions);
+    if clause
+        .get("negate")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        Ok(not(condition))
+    } else {
+        Ok(condition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::{convert, ConvertSource};
+
+    /// A flag of the REST API flag list, trimmed to the fields the converter reads
+    const EXPORT: &str = r#"{
+      "items": [
+        {
+          "key": "new-checkout",
+          "description": "New checkout flow",
+          "kind": "boolean",
+          "variations": [
+            { "_id": "a1", "value": true, "name": "Enabled" },
+            { "_id": "a2", "value": false, "name": "Disabled" }
+          ],
+          "environments": {
+            "production": {
+              "on": true,
+              "archived": false,
+              "targets": [{ "values": ["alice", "bob"], "variation": 0 }],
+              "contextTargets": [
+                { "contextKind": "organization", "values": ["acme"], "variation": 0 }
+              ],
+              "rules": [
+                {
+                  "_id": "r1",
+                  "description": "Staff",
+                  "clauses": [
+                    { "attribute": "email", "op": "endsWith", "values": ["@example.com"], "negate": false },
+                    { "attribute": "country", "op": "in", "values": ["NL", "BE"], "negate": true }
+                  ],
+                  "variation": 0
+                },
+                {
+                  "_id": "r2",
+                  "description": "Regex",
+                  "clauses": [
+                    { "attribute": "email", "op": "matches", "values": [".*@test\\.com"], "negate": false }
+                  ],
+                  "variation": 0
+                }
+              ],
+              "fallthrough": {
+                "rollout": {
+                  "variations": [
+                    { "variation": 0, "weight": 20000 },
+                    { "variation": 1, "weight": 80000 }
+                  ]
+                }
+              },
+              "offVariation": 1,
+              "prerequisites": []
+            }
+          }
+        }
+      ]
+    }"#;
+
+    #[test]
+    fn converts_a_rest_api_export() {
+        let conversion = convert(ConvertSource::LaunchDarkly, EXPORT, None).unwrap();
+
+        assert_eq!(
+            Value::Object(conversion.flags),
+            json!({
+                "new-checkout": {
+                    "state": "ENABLED",
+                    "variants": { "Enabled": true, "Disabled": false },
+                    "defaultVariant": "Disabled",
+                    "targeting": { "if": [
+                        { "in": [{ "var": "targetingKey" }, ["alice", "bob"]] },
+                        "Enabled",
+                        { "in": [{ "var": "organization.key" }, ["acme"]] },
+                        "Enabled",
+                        { "and": [
+                            { "ends_with": [{ "var": "email" }, "@example.com"] },
+                            { "!": [{ "in": [{ "var": "country" }, ["NL", "BE"]] }] },
+                        ] },
+                        "Enabled",
+                        { "fractional": [["Enabled", 20], ["Disabled", 80]] },
+                    ] },
+                    "metadata": { "description": "New checkout flow" },
+                }
+            })
+        );
+        assert_eq!(conversion.untranslated.len(), 1);
+        assert_eq!(conversion.untranslated[0].location, "rules[1] (Regex)");
+        assert_eq!(
+            conversion.untranslated[0].reason,
+            "operator 'matches' is not supported by flagd"
+        );
+    }
+
+    #[test]
+    fn converts_sdk_flag_data() {
+        let input = json!({ "flags": { "banner": {
+            "key": "banner",
+            "on": true,
+            "variations": ["red", "blue"],
+            "rules": [{
+                "clauses": [{ "attribute": "plan", "op": "in", "values": ["pro"] }],
+                "rollout": {
+                    "bucketBy": "email",
+                    "variations": [
+                        { "variation": 0, "weight": 50000 },
+                        { "variation": 1, "weight": 50000 },
+                    ],
+                },
+            }],
+            "fallthrough": { "variation": 1 },
+            "offVariation": 0,
+        } } });
+        let conversion = super::convert(&[input], None).unwrap();
+
+        assert_eq!(
+            conversion.flags["banner"],
+            json!({
+                "state": "ENABLED",
+                "variants": { "red": "red", "blue": "blue" },
+                "defaultVariant": "blue",
+                "targeting": { "if": [
+                    { "in": [{ "var": "plan" }, ["pro"]] },
+                    { "fractional": [
+                        { "cat": [{ "var": "$flagd.flagKey" }, { "var": "email" }] },
+                        ["red", 50],
+                        ["blue", 50],
+                    ] },
+                ] },
+            })
+        );
+        assert!(conversion.untranslated.is_empty());
+    }
+
+    #[test]
+    fn requires_an_environment_of_several() {
+        let documents = [json!({ "items": [{
+            "key": "a",
+            "variations": [{ "value": true }, { "value": false }],
+            "environments": {
+                "production": { "on": true, "fallthrough": { "variation": 0 } },
+                "staging": { "on": false, "fallthrough": { "variation": 1 } },
+            },
+        }] })];
+
+        assert!(super::convert(&documents, None).is_err());
+        assert!(super::convert(&documents, Some("test")).is_err());
+
+        let conversion = super::convert(&documents, Some("staging")).unwrap();
+        assert_eq!(conversion.flags["a"]["state"], "DISABLED");
+    }
+}
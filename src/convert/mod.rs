@@ -0,0 +1,470 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use utoipa::ToSchema;
+
+use crate::lifecycle::parse_date;
+
+mod flipt;
+mod gofeatureflag;
+mod launchdarkly;
+mod unleash;
+
+/// Feature flag system an export comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConvertSource {
+    /// LaunchDarkly flag list of the REST API (`items`), or SDK flag data (`flags`)
+    LaunchDarkly,
+    /// Unleash feature export or state export
+    Unleash,
+    /// Flipt export, YAML or JSON
+    Flipt,
+    /// GO Feature Flag configuration, YAML or JSON
+    GoFeatureFlag,
+}
+
+/// A rule or setting of the source that was left out of the converted flags
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UntranslatedRule {
+    /// Key of the flag
+    #[schema(example = "new-checkout")]
+    pub flag: String,
+    /// Where the rule is in the source flag
+    #[schema(example = "rules[2]")]
+    pub location: String,
+    /// Why the rule could not be translated
+    #[schema(example = "operator 'matches' is not supported by flagd")]
+    pub reason: String,
+}
+
+/// flagd flags converted from an export
+#[derive(Debug, Default)]
+pub struct Conversion {
+    /// Flags by key, in the object form of a flagd document
+    pub flags: Map<String, Value>,
+    pub untranslated: Vec<UntranslatedRule>,
+}
+
+impl Conversion {
+    fn untranslated(&mut self, flag: &str, location: impl Into<String>, reason: impl Into<String>) {
+        self.untranslated.push(UntranslatedRule {
+            flag: flag.to_string(),
+            location: location.into(),
+            reason: reason.into(),
+        });
+    }
+}
+
+/// Convert an export of `source` to flagd flags.
+///
+/// `input` is JSON or YAML, with several YAML documents allowed. `environment`
+/// selects the environment of sources that export several.
+///
+/// Targeting is translated to JsonLogic on a best-effort basis. Rules that cannot be
+/// expressed in flagd are left out as a whole, since translating part of a rule would
+/// change who it applies to, and are listed in the conversion's `untranslated` report.
+pub fn convert(
+    source: ConvertSource,
+    input: &str,
+    environment: Option<&str>,
+) -> Result<Conversion, String> {
+    let documents = parse_documents(input)?;
+
+    let mut conversion = match source {
+        ConvertSource::LaunchDarkly => launchdarkly::convert(&documents, environment)?,
+        ConvertSource::Unleash => unleash::convert(&documents, environment)?,
+        ConvertSource::Flipt => flipt::convert(&documents)?,
+        ConvertSource::GoFeatureFlag => gofeatureflag::convert(&documents)?,
+    };
+
+    // flagd flags are typed, so all variants of a flag need values of the same type
+    let mixed = conversion
+        .flags
+        .iter()
+        .filter(|(_, flag)| {
+            let mut types = flag["variants"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(_, value)| variant_type(value));
+            let first = types.next();
+            first == Some(None) || types.any(|kind| kind != first.flatten())
+        })
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    for key in mixed {
+        conversion.flags.remove(&key);
+        conversion.untranslated(
+            &key,
+            "variants",
+            "the variants have values of different types, which flagd does not support, and the flag was not converted",
+        );
+    }
+
+    Ok(conversion)
+}
+
+/// Type of a flagd flag serving the value, `None` for values no flag can serve
+fn variant_type(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Bool(_) => Some("boolean"),
+        Value::String(_) => Some("string"),
+        Value::Number(_) => Some("number"),
+        Value::Object(_) => Some("object"),
+        Value::Null | Value::Array(_) => None,
+    }
+}
+
+fn parse_documents(input: &str) -> Result<Vec<Value>, String> {
+    if let Ok(document) = serde_json::from_str::<Value>(input) {
+        return Ok(vec![document]);
+    }
+
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(input) {
+        let document = Value::deserialize(document)
+            .map_err(|e| format!("Input is neither JSON nor YAML: {}", e))?;
+        if !document.is_null() {
+            documents.push(document);
+        }
+    }
+
+    Ok(documents)
+}
+
+/// The environment to convert, `requested` or else the only one there is
+fn select_environment<'a>(
+    requested: Option<&'a str>,
+    available: &'a [String],
+) -> Result<Option<&'a str>, String> {
+    match (requested, available) {
+        (Some(environment), _)
+            if available.is_empty() || available.iter().any(|e| e == environment) =>
+        {
+            Ok(Some(environment))
+        }
+        (Some(environment), _) => Err(format!(
+            "Environment '{}' not found, available: {}",
+            environment,
+            available.join(", ")
+        )),
+        (None, []) => Ok(None),
+        (None, [environment]) => Ok(Some(environment)),
+        (None, _) => Err(format!(
+            "The export has several environments, choose one with `environment`: {}",
+            available.join(", ")
+        )),
+    }
+}
+
+/// A flagd flag
+fn flag(
+    enabled: bool,
+    variants: Map<String, Value>,
+    default_variant: &str,
+    targeting: Option<Value>,
+    metadata: Map<String, Value>,
+) -> Value {
+    let mut flag = json!({
+        "state": if enabled { "ENABLED" } else { "DISABLED" },
+        "variants": variants,
+        "defaultVariant": default_variant,
+    });
+    if let Some(targeting) = targeting {
+        flag["targeting"] = targeting;
+    }
+    if !metadata.is_empty() {
+        flag["metadata"] = Value::Object(metadata);
+    }
+    flag
+}
+
+/// Metadata entry of a non-empty string, flagd metadata only holds scalar values
+fn insert_text(metadata: &mut Map<String, Value>, key: &str, value: Option<&Value>) {
+    if let Some(text) = value
+        .and_then(Value::as_str)
+        .filter(|text| !text.is_empty())
+    {
+        metadata.insert(key.to_string(), Value::String(text.to_string()));
+    }
+}
+
+/// `{"if": [condition, result, ..., fallback]}` of the rules evaluated in order, or
+/// just the fallback without rules. A missing fallback makes flagd serve the default
+/// variant.
+fn first_match(branches: Vec<(Value, Value)>, fallback: Option<Value>) -> Option<Value> {
+    if branches.is_empty() {
+        return fallback;
+    }
+
+    let mut args = branches
+        .into_iter()
+        .flat_map(|(condition, result)| [condition, result])
+        .collect::<Vec<_>>();
+    args.extend(fallback);
+    Some(json!({ "if": args }))
+}
+
+fn var(name: &str) -> Value {
+    json!({ "var": name })
+}
+
+fn not(condition: Value) -> Value {
+    json!({ "!": [condition] })
+}
+
+fn all_of(mut conditions: Vec<Value>) -> Value {
+    match conditions.len() {
+        0 => Value::Bool(true),
+        1 => conditions.remove(0),
+        _ => json!({ "and": conditions }),
+    }
+}
+
+fn any_of(mut conditions: Vec<Value>) -> Value {
+    match conditions.len() {
+        0 => Value::Bool(false),
+        1 => conditions.remove(0),
+        _ => json!({ "or": conditions }),
+    }
+}
+
+/// Bucketing expression of a `fractional` split by `attribute`, seeded with `seed`
+/// or else the flag key like flagd's own bucketing
+fn bucket_by(seed: Option<&str>, attribute: &str) -> Value {
+    let seed = match seed {
+        Some(seed) => Value::String(seed.to_string()),
+        None => var("$flagd.flagKey"),
+    };
+    json!({ "cat": [seed, var(attribute)] })
+}
+
+/// A `fractional` split of the weighted variants in whole percentages, or just the
+/// variant if there is only one. Weights are relative, buckets of the same variant
+/// are combined and variants without weight are left out.
+fn fractional(bucket_by: Option<Value>, weights: &[(String, f64)]) -> Option<Value> {
+    let mut combined: Vec<(String, f64)> = Vec::new();
+    for (name, weight) in weights {
+        match combined.iter_mut().find(|(existing, _)| existing == name) {
+            Some((_, total)) => *total += weight.max(0.0),
+            None => combined.push((name.clone(), weight.max(0.0))),
+        }
+    }
+    let total = combined.iter().map(|(_, weight)| weight).sum::<f64>();
+    if total <= 0.0 {
+        return None;
+    }
+
+    // Largest remainder rounding, so the percentages add up to exactly 100
+    let exact = combined
+        .iter()
+        .map(|(_, weight)| weight / total * 100.0)
+        .collect::<Vec<_>>();
+    let mut percentages = exact.iter().map(|p| p.floor() as u64).collect::<Vec<_>>();
+    let mut by_remainder = (0..exact.len()).collect::<Vec<_>>();
+    by_remainder
+        .sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+    let missing = 100 - percentages.iter().sum::<u64>() as usize;
+    for &index in by_remainder.iter().take(missing) {
+        percentages[index] += 1;
+    }
+
+    let buckets = combined
+        .iter()
+        .zip(percentages)
+        .filter(|(_, percentage)| *percentage > 0)
+        .map(|((name, _), percentage)| json!([name, percentage]))
+        .collect::<Vec<_>>();
+    if let [bucket] = buckets.as_slice() {
+        return Some(bucket[0].clone());
+    }
+
+    let mut args = Vec::new();
+    args.extend(bucket_by);
+    args.extend(buckets);
+    Some(json!({ "fractional": args }))
+}
+
+/// The variant with the largest weight
+fn heaviest(weights: &[(String, f64)]) -> Option<&str> {
+    weights
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(name, _)| name.as_str())
+}
+
+/// A name for each variant value that is unique among `names`
+fn unique_name(names: &[String], preferred: String, index: usize) -> String {
+    if names.contains(&preferred) {
+        format!("{}-{}", preferred, index)
+    } else {
+        preferred
+    }
+}
+
+/// Unix timestamp of an RFC 3339 date and time in UTC, as compared with
+/// `$flagd.timestamp`
+fn parse_timestamp(value: &str) -> Option<i64> {
+    let days = parse_date(value)?;
+    let time = value
+        .split_once('T')
+        .map(|(_, time)| time)
+        .unwrap_or("00:00:00Z");
+    let time = time
+        .strip_suffix('Z')
+        .or_else(|| time.strip_suffix("+00:00"))?;
+    let time = time.split_once('.').map(|(time, _)| time).unwrap_or(time);
+
+    let mut parts = time.splitn(3, ':').map(|part| part.parse::<i64>().ok());
+    let hours = parts.next().flatten()?;
+    let minutes = parts.next().flatten()?;
+    let seconds = parts.next().flatten().unwrap_or(0);
+
+    Some(days * 86_400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weights(weights: &[(&str, f64)]) -> Vec<(String, f64)> {
+        weights
+            .iter()
+            .map(|(name, weight)| (name.to_string(), *weight))
+            .collect()
+    }
+
+    #[test]
+    fn fractional_rounds_to_whole_percentages_adding_up_to_100() {
+        let split = fractional(None, &weights(&[("a", 1.0), ("b", 1.0), ("c", 1.0)]));
+        assert_eq!(
+            split,
+            Some(json!({ "fractional": [["a", 34], ["b", 33], ["c", 33]] }))
+        );
+    }
+
+    #[test]
+    fn fractional_weights_are_relative() {
+        let split = fractional(None, &weights(&[("on", 25_000.0), ("off", 75_000.0)]));
+        assert_eq!(
+            split,
+            Some(json!({ "fractional": [["on", 25], ["off", 75]] }))
+        );
+    }
+
+    #[test]
+    fn fractional_combines_buckets_of_the_same_variant() {
+        let split = fractional(None, &weights(&[("on", 10.0), ("off", 50.0), ("on", 40.0)]));
+        assert_eq!(
+            split,
+            Some(json!({ "fractional": [["on", 50], ["off", 50]] }))
+        );
+    }
+
+    #[test]
+    fn fractional_of_a_single_variant_is_the_variant() {
+        let split = fractional(
+            Some(bucket_by(None, "email")),
+            &weights(&[("on", 100.0), ("off", 0.0), ("gone", -5.0)]),
+        );
+        assert_eq!(split, Some(json!("on")));
+    }
+
+    #[test]
+    fn fractional_without_weights_is_none() {
+        assert_eq!(fractional(None, &[]), None);
+        assert_eq!(
+            fractional(None, &weights(&[("on", 0.0), ("off", -1.0)])),
+            None
+        );
+    }
+
+    #[test]
+    fn fractional_starts_with_the_bucketing() {
+        let split = fractional(
+            Some(bucket_by(None, "email")),
+            &weights(&[("on", 1.0), ("off", 3.0)]),
+        );
+        assert_eq!(
+            split,
+            Some(json!({ "fractional": [
+                { "cat": [{ "var": "$flagd.flagKey" }, { "var": "email" }] },
+                ["on", 25],
+                ["off", 75],
+            ] }))
+        );
+    }
+
+    #[test]
+    fn bucket_by_is_seeded_with_the_flag_key_by_default() {
+        assert_eq!(
+            bucket_by(None, "targetingKey"),
+            json!({ "cat": [{ "var": "$flagd.flagKey" }, { "var": "targetingKey" }] })
+        );
+        assert_eq!(
+            bucket_by(Some("checkout"), "sessionId"),
+            json!({ "cat": ["checkout", { "var": "sessionId" }] })
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_of_dates_and_times_in_utc() {
+        assert_eq!(parse_timestamp("1970-01-02"), Some(86_400));
+        assert_eq!(parse_timestamp("2025-01-01T00:00:00Z"), Some(1_735_689_600));
+        assert_eq!(
+            parse_timestamp("2025-01-01T12:30:15+00:00"),
+            Some(1_735_689_600 + 12 * 3600 + 30 * 60 + 15)
+        );
+        assert_eq!(
+            parse_timestamp("2025-01-01T12:30:15.250Z"),
+            parse_timestamp("2025-01-01T12:30:15Z")
+        );
+        assert_eq!(
+            parse_timestamp("2025-01-01T12:30Z"),
+            Some(1_735_689_600 + 12 * 3600 + 30 * 60)
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_other_offsets_and_invalid_dates() {
+        assert_eq!(parse_timestamp("2025-01-01T12:00:00+02:00"), None);
+        assert_eq!(parse_timestamp("2025-01-01T12:00:00"), None);
+        assert_eq!(parse_timestamp("2025-02-30T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("2025-01-01Tnoon:00Z"), None);
+        assert_eq!(parse_timestamp("tomorrow"), None);
+    }
+
+    #[test]
+    fn drops_flags_with_variants_of_different_types() {
+        let input = r#"
+mixed:
+  variations: { a: true, b: "yes" }
+  defaultRule: { variation: a }
+typed:
+  variations: { a: 1, b: 2 }
+  defaultRule: { variation: b }
+"#;
+        let conversion = convert(ConvertSource::GoFeatureFlag, input, None).unwrap();
+
+        assert_eq!(conversion.flags.keys().collect::<Vec<_>>(), ["typed"]);
+        assert_eq!(conversion.untranslated.len(), 1);
+        assert_eq!(conversion.untranslated[0].flag, "mixed");
+        assert_eq!(conversion.untranslated[0].location, "variants");
+    }
+
+    #[test]
+    fn select_environment_picks_the_only_one() {
+        let available = ["production".to_string()];
+        assert_eq!(select_environment(None, &available), Ok(Some("production")));
+        assert_eq!(select_environment(None, &[]), Ok(None));
+        assert!(select_environment(Some("staging"), &available).is_err());
+
+        let several = ["production".to_string(), "staging".to_string()];
+        assert!(select_environment(None, &several).is_err());
+        assert_eq!(
+            select_environment(Some("staging"), &several),
+            Ok(Some("staging"))
+        );
+    }
+}
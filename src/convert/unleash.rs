@@ -0,0 +1,705 @@
+use serde_json::{json, Map, Value};
+
+use super::{
+    all_of, any_of, bucket_by, first_match, flag, fractional, heaviest, insert_text, not,
+    parse_timestamp, select_environment, var, Conversion,
+};
+
+/// Variant Unleash returns when a flag is not enabled for a context
+const DISABLED_VARIANT: &str = "disabled";
+
+/// A strategy's condition, if it has one, and its rollout percentage and bucketing
+type Strategy = (Option<Value>, Option<(f64, Option<Value>)>);
+
+/// Convert a feature export, or a state export of older Unleash versions whose
+/// features hold their own strategies
+pub(super) fn convert(
+    documents: &[Value],
+    environment: Option<&str>,
+) -> Result<Conversion, String> {
+    let mut conversion = Conversion::default();
+
+    for document in documents {
+        let features = document
+            .get("features")
+            .and_then(Value::as_array)
+            .ok_or("Expected an Unleash export with `features`")?;
+        let strategies = array(document, "featureStrategies");
+        let feature_environments = array(document, "featureEnvironments");
+        let segments = array(document, "segments");
+
+        let mut available = feature_environments
+            .iter()
+            .chain(strategies)
+            .filter_map(|entry| entry.get("environment").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        available.sort();
+        available.dedup();
+        let environment = select_environment(environment, &available)?;
+        let in_environment = |entry: &&Value| match (
+            environment,
+            entry.get("environment").and_then(Value::as_str),
+        ) {
+            (Some(environment), Some(entry)) => environment == entry,
+            _ => true,
+        };
+
+        for feature in features {
+            let name = feature
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or("Unleash feature without a name")?;
+            let of_feature =
+                |entry: &&Value| entry.get("featureName").and_then(Value::as_str) == Some(name);
+
+            let feature_environment = feature_environments
+                .iter()
+                .filter(of_feature)
+                .find(in_environment);
+            let enabled = feature_environment
+                .and_then(|entry| entry.get("enabled"))
+                .or_else(|| feature.get("enabled"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let variants = feature_environment
+                .and_then(|entry| entry.get("variants"))
+                .and_then(Value::as_array)
+                .filter(|variants| !variants.is_empty())
+                .or_else(|| feature.get("variants").and_then(Value::as_array))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            let mut feature_strategies = strategies
+                .iter()
+                .filter(of_feature)
+                .filter(in_environment)
+                .collect::<Vec<_>>();
+            if feature_strategies.is_empty() {
+                feature_strategies = array(feature, "strategies").iter().collect();
+            }
+            feature_strategies
+                .sort_by_key(|strategy| strategy.get("sortOrder").and_then(Value::as_i64));
+
+            let mut metadata = Map::new();
+            insert_text(&mut metadata, "description", feature.get("description"));
+            convert_feature(
+                name,
+                enabled,
+                variants,
+                &feature_strategies,
+                segments,
+                metadata,
+                &mut conversion,
+            );
+        }
+    }
+
+    Ok(conversion)
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn convert_feature(
+    name: &str,
+    enabled: bool,
+    variants: &[Value],
+    strategies: &[&Value],
+    segments: &[Value],
+    metadata: Map<String, Value>,
+    conversion: &mut Conversion,
+) {
+    let strategy_variants = |strategy: &Value| array(strategy, "variants").to_vec();
+    let has_variants = !variants.is_empty()
+        || strategies
+            .iter()
+            .any(|strategy| !strategy_variants(strategy).is_empty());
+
+    let mut values = Map::new();
+    let (on_weights, off) = if has_variants {
+        for variant in variants.iter().chain(
+            strategies
+                .iter()
+                .flat_map(|strategy| array(strategy, "variants")),
+        ) {
+            if let Some(variant_name) = variant.get("name").and_then(Value::as_str) {
+                values.insert(
+                    variant_name.to_string(),
+                    payload_value(variant, variant_name),
+                );
+            }
+        }
+        (weights(variants), DISABLED_VARIANT)
+    } else {
+        values.insert("on".to_string(), Value::Bool(true));
+        values.insert("off".to_string(), Value::Bool(false));
+        (vec![("on".to_string(), 1.0)], "off")
+    };
+
+    // Variants of strategies without their own go to `disabled` if the flag has none
+    let mut needs_off = on_weights.is_empty();
+    let on_weights = if needs_off {
+        vec![(off.to_string(), 1.0)]
+    } else {
+        on_weights
+    };
+
+    // Overrides of older Unleash versions pin context values to a variant
+    let mut branches = Vec::new();
+    for variant in variants {
+        let overrides = array(variant, "overrides");
+        let Some(variant_name) = variant.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        if overrides.is_empty() {
+            continue;
+        }
+        let condition = any_of(
+            overrides
+                .iter()
+                .map(|entry| {
+                    let context = entry
+                        .get("contextName")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+                    json!({ "in": [var(context_field(context)), entry.get("values")] })
+                })
+                .collect(),
+        );
+        branches.push((condition, Value::String(variant_name.to_string())));
+    }
+
+    // Without any strategy an enabled flag is on for everyone
+    let mut fallback = strategies
+        .is_empty()
+        .then(|| fractional(variants_bucket(variants), &on_weights))
+        .flatten();
+    let active = strategies
+        .iter()
+        .filter(|strategy| {
+            !strategy
+                .get("disabled")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+
+    for (index, strategy) in active.iter().enumerate() {
+        let strategy_name = strategy
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("default");
+        let location = format!("strategies[{}] ({})", index, strategy_name);
+        let (condition, rollout) = match translate_strategy(name, strategy, segments) {
+            Ok(translated) => translated,
+            Err(reason) => {
+                conversion.untranslated(name, location, reason);
+                continue;
+            }
+        };
+
+        let own_variants = strategy_variants(strategy);
+        let (mut result_weights, variants_bucket) = if own_variants.is_empty() {
+            (on_weights.clone(), variants_bucket(variants))
+        } else {
+            (weights(&own_variants), variants_bucket(&own_variants))
+        };
+        let bucket = match rollout {
+            Some((percentage, bucket)) => {
+                let total = result_weights.iter().map(|(_, weight)| weight).sum::<f64>();
+                for (_, weight) in &mut result_weights {
+                    *weight = *weight / total * percentage;
+                }
+                result_weights.push((off.to_string(), 100.0 - percentage));
+                needs_off = true;
+                if index + 1 < active.len() {
+                    conversion.untranslated(
+                        name,
+                        location.clone(),
+                        "users outside the rollout are not checked against the strategies that follow",
+                    );
+                }
+                bucket
+            }
+            None => variants_bucket,
+        };
+        let Some(result) = fractional(bucket, &result_weights) else {
+            conversion.untranslated(name, location, "the variants have no weights");
+            continue;
+        };
+
+        match condition {
+            Some(condition) => branches.push((condition, result)),
+            None => {
+                fallback = Some(result);
+                break;
+            }
+        }
+    }
+
+    // Contexts no strategy matches get the flag's off variant
+    needs_off |= fallback.is_none();
+    let default_variant = match &fallback {
+        Some(Value::String(variant)) => {
+            let variant = variant.clone();
+            fallback = None;
+            variant
+        }
+        Some(_) => heaviest(&on_weights).unwrap_or(off).to_string(),
+        None => off.to_string(),
+    };
+
+    if has_variants && (needs_off || !enabled || default_variant == off) {
+        // Typed like the other variants, so clients get the type they ask for
+        let disabled = match values.values().next() {
+            Some(Value::Number(_)) => json!(0),
+            Some(Value::Bool(_)) => json!(false),
+            Some(Value::Object(_)) => json!({}),
+            _ => json!(DISABLED_VARIANT),
+        };
+        values.entry(DISABLED_VARIANT).or_insert(disabled);
+    }
+
+    conversion.flags.insert(
+        name.to_string(),
+        flag(
+            enabled,
+            values,
+            &default_variant,
+            first_match(branches, fallback),
+            metadata,
+        ),
+    );
+}
+
+/// Value of a variant: its payload, or else its name
+fn payload_value(variant: &Value, name: &str) -> Value {
+    let payload = variant.get("payload");
+    let kind = payload.and_then(|p| p.get("type")).and_then(Value::as_str);
+    let Some(value) = payload.and_then(|p| p.get("value")).and_then(Value::as_str) else {
+        return Value::String(name.to_string());
+    };
+
+    let parsed = match kind {
+        Some("json") => serde_json::from_str::<Value>(value).ok(),
+        Some("number") => serde_json::from_str::<Value>(value)
+            .ok()
+            .filter(Value::is_number),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+fn weights(variants: &[Value]) -> Vec<(String, f64)> {
+    variants
+        .iter()
+        .filter_map(|variant| {
+            let name = variant.get("name").and_then(Value::as_str)?;
+            let weight = variant.get("weight").and_then(Value::as_f64).unwrap_or(0.0);
+            Some((name.to_string(), weight))
+        })
+        .collect()
+}
+
+/// Bucketing of the variant split, by the stickiness of the variants
+fn variants_bucket(variants: &[Value]) -> Option<Value> {
+    let stickiness = variants
+        .first()
+        .and_then(|variant| variant.get("stickiness"))
+        .and_then(Value::as_str);
+    stickiness_bucket(stickiness, None).ok().flatten()
+}
+
+/// Bucketing expression of a stickiness, where `default` is the user id
+fn stickiness_bucket(
+    stickiness: Option<&str>,
+    group_id: Option<&str>,
+) -> Result<Option<Value>, String> {
+    match stickiness.unwrap_or("default") {
+        "random" => Err("random stickiness is not supported by flagd".to_string()),
+        "default" | "userId" => {
+            Ok(group_id.map(|group_id| bucket_by(Some(group_id), "targetingKey")))
+        }
+        field => Ok(Some(bucket_by(group_id, field))),
+    }
+}
+
+/// Flag evaluation context field of an Unleash context field
+fn context_field(name: &str) -> &str {
+    match name {
+        "userId" => "targetingKey",
+        name => name,
+    }
+}
+
+fn translate_strategy(
+    feature: &str,
+    strategy: &Value,
+    segments: &[Value],
+) -> Result<Strategy, String> {
+    let parameter = |key: &str| match strategy.get("parameters").and_then(|p| p.get(key)) {
+        Some(Value::String(value)) => Some(value.clone()),
+        Some(Value::Number(value)) => Some(value.to_string()),
+        _ => None,
+    };
+    let list = |key: &str| {
+        parameter(key)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let percentage = |key: &str| match parameter(key) {
+        None => Ok(100.0),
+        Some(value) => value
+            .parse::<f64>()
+            .map(|percentage| percentage.clamp(0.0, 100.0))
+            .map_err(|_| format!("invalid rollout percentage '{}'", value)),
+    };
+    // The group id seeds the bucketing, Unleash defaults it to the feature name
+    let group_id = parameter("groupId").filter(|group_id| group_id != feature);
+
+    let mut conditions = array(strategy, "constraints")
+        .iter()
+        .map(constraint)
+        .collect::<Result<Vec<_>, _>>()?;
+    for id in array(strategy, "segments") {
+        let segment = segments
+            .iter()
+            .find(|segment| segment.get("id") == Some(id))
+            .ok_or_else(|| format!("segment {} is not part of the export", id))?;
+        for condition in array(segment, "constraints") {
+            conditions.push(constraint(condition)?);
+        }
+    }
+
+    let rollout = match strategy
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("default")
+    {
+        "default" => None,
+        "flexibleRollout" => {
+            let stickiness = parameter("stickiness");
+            Some((
+                percentage("rollout")?,
+                stickiness_bucket(stickiness.as_deref(), group_id.as_deref())?,
+            ))
+        }
+        "gradualRolloutUserId" => Some((
+            percentage("percentage")?,
+            stickiness_bucket(Some("userId"), group_id.as_deref())?,
+        )),
+        "gradualRolloutSessionId" => Some((
+            percentage("percentage")?,
+            stickiness_bucket(Some("sessionId"), group_id.as_deref())?,
+        )),
+        "gradualRolloutRandom" => {
+            return Err("random rollouts are not supported by flagd".to_string())
+        }
+        "userWithId" => {
+            conditions.push(json!({ "in": [var("targetingKey"), list("userIds")] }));
+            None
+        }
+        "applicationHostname" => {
+            conditions.push(json!({ "in": [var("hostname"), list("hostNames")] }));
+            None
+        }
+        "remoteAddress" => {
+            let addresses = list("IPs");
+            if addresses.iter().any(|address| address.contains('/')) {
+                return Err("IP ranges are not supported by flagd".to_string());
+            }
+            conditions.push(json!({ "in": [var("remoteAddress"), addresses] }));
+            None
+        }
+        name => {
+            return Err(format!(
+                "custom strategy '{}' is not supported by flagd",
+                name
+            ))
+        }
+    };
+
+    let condition = (!conditions.is_empty()).then(|| all_of(conditions));
+    Ok((
+        condition,
+        rollout.filter(|(percentage, _)| *percentage < 100.0),
+    ))
+}
+
+/// JsonLogic condition of a strategy or segment constraint
+fn constraint(constraint: &Value) -> Result<Value, String> {
+    let context = constraint
+        .get("contextName")
+        .and_then(Value::as_str)
+        .ok_or("constraint without a context field")?;
+    let operator = constraint
+        .get("operator")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let field = var(context_field(context));
+    let mut values = array(constraint, "values").to_vec();
+    values.extend(constraint.get("value").cloned());
+
+    if constraint.get("caseInsensitive").and_then(Value::as_bool) == Some(true)
+        && operator.starts_with("STR_")
+    {
+        return Err("case-insensitive matching is not supported by flagd".to_string());
+    }
+
+    let compare = |op: &str, number: bool| {
+        values
+            .iter()
+            .map(|value| {
+                let value = match value {
+                    Value::String(text) if number => serde_json::from_str::<Value>(text)
+                        .ok()
+                        .filter(Value::is_number)
+                        .ok_or_else(|| format!("'{}' is not a number", text))?,
+                    value => value.clone(),
+                };
+                Ok(json!({ op: [field, value] }))
+            })
+            .collect::<Result<Vec<_>, String>>()
+    };
+    let sem_ver = |op: &str| {
+        values
+            .iter()
+            .map(|value| json!({ "sem_ver": [field, op, value] }))
+            .collect::<Vec<_>>()
+    };
+    let date = |op: &str| {
+        if context != "currentTime" {
+            return Err(format!(
+                "date comparisons of '{}' are not supported by flagd",
+                context
+            ));
+        }
+        values
+            .iter()
+            .map(|value| {
+                let timestamp = value
+                    .as_str()
+                    .and_then(parse_timestamp)
+                    .ok_or_else(|| format!("invalid date {}", value))?;
+                Ok(json!({ op: [var("$flagd.timestamp"), timestamp] }))
+            })
+            .collect::<Result<Vec<_>, String>>()
+    };
+
+    let conditions = match operator {
+        "IN" => vec![json!({ "in": [field, values] })],
+        "NOT_IN" => vec![not(json!({ "in": [field, values] }))],
+        "STR_CONTAINS" => values
+            .iter()
+            .map(|value| json!({ "in": [value, field] }))
+            .collect(),
+        "STR_STARTS_WITH" => compare("starts_with", false)?,
+        "STR_ENDS_WITH" => compare("ends_with", false)?,
+        "NUM_EQ" => compare("==", true)?,
+        "NUM_GT" => compare(">", true)?,
+        "NUM_GTE" => compare(">=", true)?,
+        "NUM_LT" => compare("<", true)?,
+        "NUM_LTE" => compare("<=", true)?,
+        "DATE_AFTER" => date(">")?,
+        "DATE_BEFORE" => date("<")?,
+        "SEMVER_EQ" => sem_ver("="),
+        "SEMVER_GT" => sem_ver(">"),
+        "SEMVER_LT" => sem_ver("<"),
+        operator => return Err(format!("operator '{}' is not supported by flagd", operator)),
+    };
+
+    let condition = any_of(conditions);
+    if constraint.get("inverted").and_then(Value::as_bool) == Some(true) {
+        Ok(not(condition))
+    } else {
+        Ok(condition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::{convert, ConvertSource};
+
+    /// A feature export of Unleash 5, trimmed to the fields the converter reads
+    const EXPORT: &str = r#"{
+      "features": [
+        {
+          "name": "new-checkout",
+          "description": "New checkout flow",
+          "type": "release",
+          "project": "default",
+          "stale": false,
+          "impressionData": false
+        }
+      ],
+      "featureStrategies": [
+        {
+          "name": "flexibleRollout",
+          "id": "6c1ba6f1",
+          "featureName": "new-checkout",
+          "environment": "production",
+          "parameters": { "groupId": "new-checkout", "rollout": "25", "stickiness": "default" },
+          "constraints": [
+            { "values": ["NL", "BE"], "inverted": false, "operator": "IN", "contextName": "country", "caseInsensitive": false }
+          ],
+          "segments": [1],
+          "sortOrder": 0
+        },
+        {
+          "name": "userWithId",
+          "id": "0f4ee1b3",
+          "featureName": "new-checkout",
+          "environment": "production",
+          "parameters": { "userIds": "alice, bob" },
+          "constraints": [],
+          "sortOrder": 1
+        }
+      ],
+      "featureEnvironments": [
+        { "enabled": true, "featureName": "new-checkout", "environment": "production", "variants": [] }
+      ],
+      "segments": [
+        {
+          "id": 1,
+          "name": "beta",
+          "constraints": [
+            { "values": [], "value": "2.0.0", "inverted": false, "operator": "SEMVER_GT", "contextName": "appVersion", "caseInsensitive": false }
+          ]
+        }
+      ]
+    }"#;
+
+    #[test]
+    fn converts_a_feature_export() {
+        let conversion = convert(ConvertSource::Unleash, EXPORT, None).unwrap();
+
+        assert_eq!(
+            Value::Object(conversion.flags),
+            json!({
+                "new-checkout": {
+                    "state": "ENABLED",
+                    "variants": { "on": true, "off": false },
+                    "defaultVariant": "off",
+                    "targeting": { "if": [
+                        { "and": [
+                            { "in": [{ "var": "country" }, ["NL", "BE"]] },
+                            { "sem_ver": [{ "var": "appVersion" }, ">", "2.0.0"] },
+                        ] },
+                        { "fractional": [["on", 25], ["off", 75]] },
+                        { "in": [{ "var": "targetingKey" }, ["alice", "bob"]] },
+                        "on",
+                    ] },
+                    "metadata": { "description": "New checkout flow" },
+                }
+            })
+        );
+        assert_eq!(conversion.untranslated.len(), 1);
+        assert_eq!(
+            conversion.untranslated[0].location,
+            "strategies[0] (flexibleRollout)"
+        );
+    }
+
+    #[test]
+    fn converts_variants_of_a_state_export() {
+        let input = json!({ "features": [{
+            "name": "banner",
+            "enabled": true,
+            "strategies": [{ "name": "default", "parameters": {} }],
+            "variants": [
+                {
+                    "name": "red",
+                    "weight": 600,
+                    "stickiness": "sessionId",
+                    "payload": { "type": "string", "value": "#f00" },
+                    "overrides": [{ "contextName": "userId", "values": ["alice"] }],
+                },
+                {
+                    "name": "blue",
+                    "weight": 400,
+                    "stickiness": "sessionId",
+                    "payload": { "type": "string", "value": "#00f" },
+                },
+            ],
+        }] });
+        let conversion = super::convert(&[input], None).unwrap();
+
+        assert_eq!(
+            conversion.flags["banner"],
+            json!({
+                "state": "ENABLED",
+                "variants": { "red": "#f00", "blue": "#00f" },
+                "defaultVariant": "red",
+                "targeting": { "if": [
+                    { "in": [{ "var": "targetingKey" }, ["alice"]] },
+                    "red",
+                    { "fractional": [
+                        { "cat": [{ "var": "$flagd.flagKey" }, { "var": "sessionId" }] },
+                        ["red", 60],
+                        ["blue", 40],
+                    ] },
+                ] },
+            })
+        );
+        assert!(conversion.untranslated.is_empty());
+    }
+
+    #[test]
+    fn reports_strategies_flagd_cannot_express() {
+        let input = json!({ "features": [{
+            "name": "a",
+            "enabled": true,
+            "strategies": [
+                { "name": "remoteAddress", "parameters": { "IPs": "10.0.0.0/8" } },
+                {
+                    "name": "default",
+                    "constraints": [{
+                        "contextName": "email",
+                        "operator": "STR_ENDS_WITH",
+                        "values": ["@example.com"],
+                        "caseInsensitive": true,
+                    }],
+                },
+                { "name": "my-strategy" },
+            ],
+        }] });
+        let conversion = super::convert(&[input], None).unwrap();
+
+        let reasons = conversion
+            .untranslated
+            .iter()
+            .map(|rule| (rule.location.as_str(), rule.reason.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            [
+                (
+                    "strategies[0] (remoteAddress)",
+                    "IP ranges are not supported by flagd"
+                ),
+                (
+                    "strategies[1] (default)",
+                    "case-insensitive matching is not supported by flagd"
+                ),
+                (
+                    "strategies[2] (my-strategy)",
+                    "custom strategy 'my-strategy' is not supported by flagd"
+                ),
+            ]
+        );
+        // With no strategy left, no context gets the flag
+        assert_eq!(conversion.flags["a"]["defaultVariant"], "off");
+        assert!(conversion.flags["a"].get("targeting").is_none());
+    }
+}
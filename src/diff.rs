@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use utoipa::ToSchema;
+
+use crate::document::{escape_pointer, flag_entries};
+
+/// Kind of change between two versions of a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A single changed field
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FieldChange {
+    /// JSON pointer to the field, relative to the flag or the flag set
+    #[schema(example = "/defaultVariant")]
+    pub path: String,
+    /// Kind of the change
+    pub kind: ChangeKind,
+    /// Value on the left side, absent for additions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Object)]
+    pub before: Option<Value>,
+    /// Value on the right side, absent for removals
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Object)]
+    pub after: Option<Value>,
+}
+
+/// Changes of a flag that exists on both sides
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FlagDiff {
+    /// Key of the flag
+    #[schema(example = "new-checkout")]
+    pub key: String,
+    /// Changed fields of the flag
+    pub changes: Vec<FieldChange>,
+}
+
+/// Flag-aware difference between two flagd documents
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct FlagSetDiff {
+    /// Keys of flags that only exist on the right side
+    pub added: Vec<String>,
+    /// Keys of flags that only exist on the left side
+    pub removed: Vec<String>,
+    /// Flags that exist on both sides but differ
+    pub modified: Vec<FlagDiff>,
+    /// Changes of the flag set metadata, `$evaluators` and other top-level fields
+    pub document: Vec<FieldChange>,
+}
+
+impl FlagSetDiff {
+    /// Whether both documents are semantically identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.document.is_empty()
+    }
+}
+
+/// Fields whose entries are compared one by one instead of as a whole
+const KEYED_FIELDS: &[&str] = &["variants", "metadata", "$evaluators"];
+
+/// Fields whose JSON Logic rules are compared down to the changed operands
+const RULE_FIELDS: &[&str] = &["targeting"];
+
+/// Compute the flag-aware difference between two flagd documents
+pub fn diff_documents(left: &Value, right: &Value) -> FlagSetDiff {
+    let left_flags = flags_by_key(left);
+    let right_flags = flags_by_key(right);
+
+    let mut diff = FlagSetDiff::default();
+
+    for (key, left_flag) in &left_flags {
+        match right_flags.get(key) {
+            None => diff.removed.push(key.clone()),
+            Some(right_flag) => {
+                let changes = diff_fields(left_flag, right_flag);
+                if !changes.is_empty() {
+                    diff.modified.push(FlagDiff {
+                        key: key.clone(),
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+
+    diff.added = right_flags
+        .keys()
+        .filter(|key| !left_flags.contains_key(*key))
+        .cloned()
+        .collect();
+
+    diff.document = diff_fields(&without_flags(left), &without_flags(right));
+
+    diff
+}
+
+fn flags_by_key(doc: &Value) -> BTreeMap<String, Value> {
+    flag_entries(doc)
+        .into_iter()
+        .map(|(key, flag, _)| {
+            let mut flag = flag.clone();
+            // The array form carries the key inside the flag
+            if let Some(fields) = flag.as_object_mut() {
+                fields.remove("key");
+            }
+            (key, flag)
+        })
+        .collect()
+}
+
+fn without_flags(doc: &Value) -> Value {
+    let mut doc = doc.clone();
+    if let Some(fields) = doc.as_object_mut() {
+        fields.remove("flags");
+        // `$schema` is rewritten on every write and not part of the content
+        fields.remove("$schema");
+    }
+    doc
+}
+
+fn diff_fields(left: &Value, right: &Value) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let left = left.as_object().unwrap_or(&empty);
+    let right = right.as_object().unwrap_or(&empty);
+
+    let names = left.keys().chain(right.keys()).collect::<BTreeSet<_>>();
+    let mut changes = Vec::new();
+
+    for name in names {
+        let path = format!("/{}", escape_pointer(name));
+        match (left.get(name), right.get(name)) {
+            (Some(Value::Object(before)), Some(Value::Object(after)))
+                if KEYED_FIELDS.contains(&name.as_str()) =>
+            {
+                let entries = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+                for entry in entries {
+                    let entry_path = format!("{}/{}", path, escape_pointer(entry));
+                    push_change(
+                        &mut changes,
+                        entry_path,
+                        before.get(entry),
+                        after.get(entry),
+                    );
+                }
+            }
+            (Some(before), Some(after)) if RULE_FIELDS.contains(&name.as_str()) => {
+                diff_rules(&mut changes, path, before, after)
+            }
+            (before, after) => push_change(&mut changes, path, before, after),
+        }
+    }
+
+    changes
+}
+
+/// Compare two JSON Logic rules, descending into objects and into arrays of equal
+/// length so that a changed operand is reported instead of the whole rule
+fn diff_rules(changes: &mut Vec<FieldChange>, path: String, before: &Value, after: &Value) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let names = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+            for name in names {
+                let path = format!("{}/{}", path, escape_pointer(name));
+                match (before.get(name), after.get(name)) {
+                    (Some(before), Some(after)) => diff_rules(changes, path, before, after),
+                    (before, after) => push_change(changes, path, before, after),
+                }
+            }
+        }
+        (Value::Array(before), Value::Array(after)) if before.len() == after.len() => {
+            for (index, (before, after)) in before.iter().zip(after).enumerate() {
+                diff_rules(changes, format!("{}/{}", path, index), before, after);
+            }
+        }
+        (before, after) => push_change(changes, path, Some(before), Some(after)),
+    }
+}
+
+fn push_change(
+    changes: &mut Vec<FieldChange>,
+    path: String,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) {
+    let kind = match (before, after) {
+        (None, Some(_)) => ChangeKind::Added,
+        (Some(_), None) => ChangeKind::Removed,
+        (Some(before), Some(after)) if before != after => ChangeKind::Modified,
+        _ => return,
+    };
+
+    changes.push(FieldChange {
+        path,
+        kind,
+        before: before.cloned(),
+        after: after.cloned(),
+    });
+}
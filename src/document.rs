@@ -0,0 +1,38 @@
+use serde_json::Value;
+
+/// Collect all flags of a document together with their JSON pointer.
+///
+/// Supports both the object form and the array form of `flags`.
+pub fn flag_entries(doc: &Value) -> Vec<(String, &Value, String)> {
+    match doc.get("flags") {
+        Some(Value::Object(flags)) => flags
+            .iter()
+            .map(|(key, flag)| (key.clone(), flag, format!("/flags/{}", escape_pointer(key))))
+            .collect(),
+        Some(Value::Array(flags)) => flags
+            .iter()
+            .enumerate()
+            .filter_map(|(index, flag)| {
+                let key = flag.get("key")?.as_str()?.to_string();
+                Some((key, flag, format!("/flags/{}", index)))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Escape a single JSON pointer reference token (RFC 6901)
+pub fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Find a flag by key for modification, in the object or the array form of `flags`
+pub fn flag_mut<'a>(doc: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    match doc.get_mut("flags")? {
+        Value::Object(flags) => flags.get_mut(key),
+        Value::Array(flags) => flags
+            .iter_mut()
+            .find(|flag| flag.get("key").and_then(Value::as_str) == Some(key)),
+        _ => None,
+    }
+}
@@ -1,51 +1,262 @@
 use axum::{
-    http::StatusCode,
+    async_trait,
+    extract::{
+        rejection::{BytesRejection, JsonRejection, QueryRejection},
+        FromRequest, FromRequestParts, Query, Request,
+    },
+    http::{header, request::Parts, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
 
-/// Custom error types for the application
+/// Custom error types for the application.
+///
+/// The message of client errors (4xx) is returned as the `detail` of the problem
+/// response and must not contain internal information. The message of
+/// [`AppError::InternalServerError`] is only logged.
 #[derive(Debug)]
 pub enum AppError {
-    NotFound(String),
+    /// Malformed request, e.g. invalid JSON or query parameters
     BadRequest(String),
+    /// Flag set name that is empty or contains path separators
+    InvalidName(String),
+    /// Document rejected by schema or semantic validation
+    ValidationFailed(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    /// Flag set already exists
+    Conflict(String),
+    /// `If-Match` does not match the current version of the flag set
+    PreconditionFailed(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
     InternalServerError(String),
 }
 
-/// JSON error response structure
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-    status: u16,
+/// Stable, machine-readable error codes of problem responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    InvalidName,
+    ValidationFailed,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PreconditionFailed,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    InternalError,
+}
+
+impl ErrorCode {
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::InvalidName => "invalid_name",
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::PreconditionFailed => "precondition_failed",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::UnsupportedMediaType => "unsupported_media_type",
+            ErrorCode::InternalError => "internal_error",
+        }
+    }
+}
+
+impl AppError {
+    /// Stable code identifying the kind of error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::InvalidName(_) => ErrorCode::InvalidName,
+            AppError::ValidationFailed(_) => ErrorCode::ValidationFailed,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Conflict(_) => ErrorCode::Conflict,
+            AppError::PreconditionFailed(_) => ErrorCode::PreconditionFailed,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::UnsupportedMediaType(_) => ErrorCode::UnsupportedMediaType,
+            AppError::InternalServerError(_) => ErrorCode::InternalError,
+        }
+    }
+
+    /// HTTP status of the error response
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::InvalidName(_) | AppError::ValidationFailed(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            AppError::BadRequest(msg)
+            | AppError::InvalidName(msg)
+            | AppError::ValidationFailed(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::PreconditionFailed(msg)
+            | AppError::PayloadTooLarge(msg)
+            | AppError::UnsupportedMediaType(msg)
+            | AppError::InternalServerError(msg) => msg,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+tokio::task_local! {
+    /// `X-Request-Id` of the request being handled, quoted in error responses
+    pub static REQUEST_ID: String;
+}
+
+/// Media type of error responses
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Error response in the RFC 7807 problem details format
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "type": "urn:flagd-ui:problem:conflict",
+    "title": "Conflict",
+    "status": 409,
+    "detail": "Flag definition 'demo' already exists",
+    "code": "conflict",
+    "requestId": "5f0c6a43-3c1e-4d8f-9a52-0f6a4c0f1d2e"
+}))]
+pub struct ProblemDetails {
+    /// URI identifying the problem type, `urn:flagd-ui:problem:<code>`
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short summary of the problem type
+    pub title: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Explanation of this occurrence of the problem
+    pub detail: String,
+    /// Stable machine-readable error code
+    pub code: ErrorCode,
+    /// `X-Request-Id` of the failed request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::NotFound(msg) => {
-                tracing::warn!(error = %msg, "Request failed with not found error");
-                (StatusCode::NOT_FOUND, "Not found".to_string())
-            }
-            AppError::BadRequest(msg) => {
-                tracing::warn!(error = %msg, "Request failed with bad request error");
-                (StatusCode::BAD_REQUEST, "Bad request".to_string())
-            }
+        let status = self.status();
+        let code = self.code();
+
+        let detail = match &self {
             AppError::InternalServerError(msg) => {
-                tracing::error!(error = %msg, "Request failed with internal server error");
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal server error".to_string(),
-                )
+                tracing::error!(error = %msg, code = code.as_str(), "Request failed");
+                "Internal server error".to_string()
+            }
+            _ => {
+                tracing::warn!(error = %self, code = code.as_str(), "Request failed");
+                self.message().to_string()
             }
         };
 
-        let body = Json(ErrorResponse {
-            error: message,
+        let body = ProblemDetails {
+            problem_type: format!("urn:flagd-ui:problem:{}", code.as_str()),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
             status: status.as_u16(),
-        });
+            detail,
+            code,
+            request_id: REQUEST_ID.try_with(Clone::clone).ok(),
+        };
+
+        let mut response = (status, Json(body)).into_response();
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        response
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(rejection.body_text()),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => {
+                AppError::UnsupportedMediaType(rejection.body_text())
+            }
+            _ => AppError::BadRequest(rejection.body_text()),
+        }
+    }
+}
+
+impl From<BytesRejection> for AppError {
+    fn from(rejection: BytesRejection) -> Self {
+        match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(rejection.body_text()),
+            _ => AppError::BadRequest(rejection.body_text()),
+        }
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+/// JSON request body extractor that reports rejections as problem responses
+pub struct AppJson<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for AppJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        Ok(AppJson(value))
+    }
+}
+
+/// Query string extractor that reports rejections as problem responses
+pub struct AppQuery<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for AppQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
 
-        (status, body).into_response()
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state).await?;
+        Ok(AppQuery(value))
     }
 }
 
@@ -0,0 +1,221 @@
+use axum::{
+    extract::{Extension, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::{
+    config::ServerConfig,
+    error::{AppError, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::{flags::rebuild_search_index, AppState},
+    schema::SchemaRegistry,
+};
+
+/// Request extension marking a TLS connection whose client presented a certificate
+/// signed by the configured client CA
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedClientCertificate;
+
+/// Result of a configuration and schema reload
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadResponse {
+    /// Whether the new configuration and schemas are active
+    pub reloaded: bool,
+    /// Why the reload failed; the previous configuration and schemas stay active
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where the active schemas were loaded from
+    #[schema(example = "bundled")]
+    pub schema_source: String,
+    /// Active schema versions
+    #[schema(example = json!(["v0"]))]
+    pub schema_versions: Vec<String>,
+    /// Changed settings that only take effect after a restart
+    #[schema(example = json!(["listener"]))]
+    pub restart_required: Vec<String>,
+}
+
+/// Re-read the configuration, recompile the schemas and swap both into the state.
+///
+/// Nothing is swapped if the new schemas fail to compile. Settings bound at startup
+/// (listener, static directory, TLS files) keep their current value.
+///
+/// Schemas and configuration are swapped one after the other, not together, so a
+/// request running meanwhile can see the new schemas with the old configuration,
+/// including the old `flags_dir` and `protected_flag_sets`. The search index is
+/// rebuilt afterwards and answers from the previous flag sets until then.
+pub fn reload_app_state(state: &AppState) -> ReloadResponse {
+    let current = state.config.load_full();
+    let mut config = match current.reload() {
+        Ok(config) => config,
+        Err(error) => return reload_failed(state, error, Vec::new()),
+    };
+
+    let mut restart_required = Vec::new();
+    if (
+        config.port,
+        &config.bind_address,
+        &config.unix_socket,
+        config.shutdown_timeout,
+    ) != (
+        current.port,
+        &current.bind_address,
+        &current.unix_socket,
+        current.shutdown_timeout,
+    ) {
+        restart_required.push("listener".to_string());
+        config.port = current.port;
+        config.bind_address = current.bind_address.clone();
+        config.unix_socket = current.unix_socket.clone();
+        config.shutdown_timeout = current.shutdown_timeout;
+    }
+    if config.log_format != current.log_format {
+        restart_required.push("log_format".to_string());
+        config.log_format = current.log_format;
+    }
+    if config.static_dir != current.static_dir {
+        restart_required.push("static_dir".to_string());
+        config.static_dir = current.static_dir.clone();
+    }
+    if (
+        &config.tls_cert_file,
+        &config.tls_key_file,
+        &config.tls_client_ca_file,
+        config.tls_client_auth,
+    ) != (
+        &current.tls_cert_file,
+        &current.tls_key_file,
+        &current.tls_client_ca_file,
+        current.tls_client_auth,
+    ) {
+        restart_required.push("tls".to_string());
+        config.tls_cert_file = current.tls_cert_file.clone();
+        config.tls_key_file = current.tls_key_file.clone();
+        config.tls_client_ca_file = current.tls_client_ca_file.clone();
+        config.tls_client_auth = current.tls_client_auth;
+    }
+
+    let schemas = match SchemaRegistry::load(config.schema_file_path.as_deref()) {
+        Ok(schemas) => schemas,
+        Err(error) => return reload_failed(state, error.to_string(), restart_required),
+    };
+
+    // Not atomic as a whole, see above
+    state.schemas.store(Arc::new(schemas));
+    state.config.store(Arc::new(config));
+    rebuild_search_index(state);
+    tracing::info!("Configuration and schemas reloaded");
+
+    reload_response(state, None, restart_required)
+}
+
+fn reload_failed(state: &AppState, error: String, restart_required: Vec<String>) -> ReloadResponse {
+    tracing::error!(error = %error, "Reload failed, keeping the previous configuration");
+    reload_response(state, Some(error), restart_required)
+}
+
+fn reload_response(
+    state: &AppState,
+    error: Option<String>,
+    restart_required: Vec<String>,
+) -> ReloadResponse {
+    let schemas = state.schemas.load();
+    ReloadResponse {
+        reloaded: error.is_none(),
+        error,
+        schema_source: schemas.source().to_string(),
+        schema_versions: schemas.versions().map(str::to_string).collect(),
+        restart_required,
+    }
+}
+
+/// Reload configuration and schemas whenever the process receives SIGHUP
+#[cfg(unix)]
+pub async fn reload_on_sighup(state: AppState) -> Result<(), String> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())
+        .map_err(|e| format!("Failed to install SIGHUP handler: {}", e))?;
+
+    while hangup.recv().await.is_some() {
+        tracing::info!("Received SIGHUP, reloading configuration");
+        reload_app_state(&state);
+    }
+
+    Ok(())
+}
+
+/// Check the client certificate or bearer token of an admin request
+fn authorize_admin(
+    config: &ServerConfig,
+    headers: &HeaderMap,
+    client_certificate: Option<VerifiedClientCertificate>,
+) -> AppResult<()> {
+    if client_certificate.is_some() {
+        return Ok(());
+    }
+
+    let Some(expected) = &config.admin_token else {
+        return Err(AppError::Forbidden(
+            "Admin endpoints are disabled, ADMIN_TOKEN is not set".to_string(),
+        ));
+    };
+
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    if !constant_time_eq(provided.as_bytes(), expected.expose().as_bytes()) {
+        return Err(AppError::Unauthorized("Invalid admin token".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Compare two byte strings without leaking the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Reload configuration and schemas without restarting the server
+#[utoipa::path(
+    post,
+    path = "/admin/reload",
+    params(
+        ("Authorization" = String, Header, description = "Bearer token configured in ADMIN_TOKEN, not needed with a verified TLS client certificate")
+    ),
+    responses(
+        (status = 200, description = "Configuration and schemas reloaded", body = ReloadResponse),
+        (status = 401, description = "Missing or invalid admin token and no verified client certificate (`unauthorized`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 403, description = "Admin endpoints are disabled, ADMIN_TOKEN is not set (`forbidden`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Reload failed, the previous configuration stays active", body = ReloadResponse)
+    ),
+    tag = "admin"
+)]
+pub async fn reload(
+    State(state): State<AppState>,
+    client_certificate: Option<Extension<VerifiedClientCertificate>>,
+    headers: HeaderMap,
+) -> AppResult<impl IntoResponse> {
+    authorize_admin(
+        &state.config.load(),
+        &headers,
+        client_certificate.map(|Extension(certificate)| certificate),
+    )?;
+
+    let response = reload_app_state(&state);
+    let status = if response.reloaded {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    Ok((status, Json(response)))
+}
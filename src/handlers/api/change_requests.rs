@@ -0,0 +1,318 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    actor::actor,
+    change_requests::{ChangeRequest, ChangeRequestStatus, ChangeRequestStore},
+    diff::diff_documents,
+    error::{AppError, AppJson, AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::flags::{
+        document_etag, etag_header, get_flag_file_path, lint_flags, read_flag_document,
+        validate_flags, write_flag_document, AppState,
+    },
+    metrics::METRICS,
+    schedules::{ScheduleStatus, ScheduleStore},
+};
+
+/// Query parameters for listing change requests
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListChangeRequestsParams {
+    /// Only list change requests in this state
+    pub status: Option<ChangeRequestStatus>,
+    /// Only list change requests of this flag set
+    pub flag_set: Option<String>,
+}
+
+/// Response for listing change requests
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListChangeRequestsResponse {
+    /// Change requests, oldest first
+    pub change_requests: Vec<ChangeRequest>,
+}
+
+/// Request payload for approving or rejecting a change request
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ReviewRequest {
+    /// Comment of the reviewer
+    #[schema(example = "Looks good")]
+    pub comment: Option<String>,
+}
+
+/// Record a proposed write to a protected flag set as a pending change request.
+///
+/// Answers `202 Accepted` with the change request instead of writing the document.
+pub(crate) fn propose_change(
+    state: &AppState,
+    headers: &HeaderMap,
+    name: &str,
+    current: &Value,
+    proposed: Value,
+) -> AppResult<Response> {
+    let request = new_change_request(headers, name, current, proposed)?;
+    ChangeRequestStore::new(&state.config.load().flags_dir).save(&request)?;
+
+    tracing::info!(
+        change_request = %request.id,
+        author = %request.author,
+        "Created change request for protected flag set"
+    );
+
+    Ok((StatusCode::ACCEPTED, Json(request)).into_response())
+}
+
+/// A pending change request of the requesting user, not yet stored
+pub(crate) fn new_change_request(
+    headers: &HeaderMap,
+    name: &str,
+    current: &Value,
+    proposed: Value,
+) -> AppResult<ChangeRequest> {
+    let author = actor(headers).ok_or_else(|| {
+        AppError::Unauthorized(format!(
+            "Flag set '{}' is protected, changes require an authenticated user",
+            name
+        ))
+    })?;
+
+    let changes = diff_documents(current, &proposed);
+    Ok(ChangeRequest::new(
+        name,
+        author,
+        document_etag(current),
+        current.clone(),
+        proposed,
+        changes,
+    ))
+}
+
+/// List change requests
+#[utoipa::path(
+    get,
+    path = "/api/change-requests",
+    params(ListChangeRequestsParams),
+    responses(
+        (status = 200, description = "Change requests, oldest first", body = ListChangeRequestsResponse),
+        (status = 400, description = "Invalid query parameters (`bad_request`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "change-requests"
+)]
+pub async fn list_change_requests(
+    State(state): State<AppState>,
+    AppQuery(params): AppQuery<ListChangeRequestsParams>,
+) -> AppResult<impl IntoResponse> {
+    let change_requests = ChangeRequestStore::new(&state.config.load().flags_dir)
+        .list()?
+        .into_iter()
+        .filter(|request| params.status.is_none_or(|status| request.status == status))
+        .filter(|request| {
+            params
+                .flag_set
+                .as_ref()
+                .is_none_or(|flag_set| request.flag_set == *flag_set)
+        })
+        .collect();
+
+    Ok(Json(ListChangeRequestsResponse { change_requests }))
+}
+
+/// Get a change request
+#[utoipa::path(
+    get,
+    path = "/api/change-requests/{id}",
+    params(
+        ("id" = String, Path, description = "Identifier of the change request")
+    ),
+    responses(
+        (status = 200, description = "The change request", body = ChangeRequest),
+        (status = 404, description = "Change request not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "change-requests"
+)]
+pub async fn get_change_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let request = ChangeRequestStore::new(&state.config.load().flags_dir).get(&id)?;
+
+    Ok(Json(request))
+}
+
+/// Approve a change request and write the proposed flag set
+///
+/// Change requests of a schedule are not written, their schedule becomes `pending`
+/// and applies the change when it is due.
+#[utoipa::path(
+    post,
+    path = "/api/change-requests/{id}/approve",
+    params(
+        ("id" = String, Path, description = "Identifier of the change request")
+    ),
+    request_body(content = Option<ReviewRequest>, description = "Optional review comment"),
+    responses(
+        (status = 200, description = "Change request approved and written, or its schedule released", body = ChangeRequest),
+        (status = 400, description = "The proposed flag set no longer passes validation (`validation_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 401, description = "No authenticated user (`unauthorized`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 403, description = "The author cannot approve their own change (`forbidden`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Change request not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Already reviewed, the flag set changed since the request was created, or its schedule was cancelled (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "change-requests"
+)]
+pub async fn approve_change_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    payload: Option<AppJson<ReviewRequest>>,
+) -> AppResult<impl IntoResponse> {
+    let flags_dir = state.config.load().flags_dir.clone();
+    let store = ChangeRequestStore::new(&flags_dir);
+    let _lock = store.lock_reviews();
+
+    let mut request = store.get(&id)?;
+    tracing::Span::current().record("flag_set", request.flag_set.as_str());
+    let reviewer = reviewer(&headers, &request)?;
+
+    if reviewer == request.author {
+        return Err(AppError::Forbidden(
+            "Change requests must be approved by a different user than their author".to_string(),
+        ));
+    }
+
+    // The flag set may have changed since the request was created
+    let file_path = get_flag_file_path(&flags_dir, &request.flag_set)?;
+    if !file_path.exists() {
+        return Err(AppError::Conflict(format!(
+            "Flag definition '{}' no longer exists",
+            request.flag_set
+        )));
+    }
+    let current = read_flag_document(&file_path)?;
+    if document_etag(&current) != request.base_etag {
+        return Err(AppError::Conflict(format!(
+            "Flag definition '{}' was modified after the change request was created",
+            request.flag_set
+        )));
+    }
+
+    validate_flags(&state.schemas.load(), &request.content)
+        .inspect_err(|_| METRICS.record_validation_failure(&request.flag_set, "schema"))?;
+    lint_flags(&request.content, false)
+        .inspect_err(|_| METRICS.record_validation_failure(&request.flag_set, "semantic"))?;
+
+    if let Some(schedule) = &request.schedule {
+        review_schedule(&flags_dir, schedule, ScheduleStatus::Pending)?;
+    } else {
+        write_flag_document(&file_path, &request.content)?;
+        state.search.update(&request.flag_set, &request.content);
+    }
+
+    let comment = payload.and_then(|AppJson(review)| review.comment);
+    request.review(ChangeRequestStatus::Approved, reviewer, comment);
+    store.save(&request)?;
+
+    tracing::info!(change_request = %request.id, "Approved change request");
+
+    if request.schedule.is_some() {
+        return Ok(Json(request).into_response());
+    }
+    Ok((etag_header(&request.content), Json(request)).into_response())
+}
+
+/// Reject a change request, and the schedule it belongs to
+#[utoipa::path(
+    post,
+    path = "/api/change-requests/{id}/reject",
+    params(
+        ("id" = String, Path, description = "Identifier of the change request")
+    ),
+    request_body(content = Option<ReviewRequest>, description = "Optional review comment"),
+    responses(
+        (status = 200, description = "Change request rejected", body = ChangeRequest),
+        (status = 401, description = "No authenticated user (`unauthorized`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Change request not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Already reviewed (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "change-requests"
+)]
+pub async fn reject_change_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    payload: Option<AppJson<ReviewRequest>>,
+) -> AppResult<impl IntoResponse> {
+    let flags_dir = state.config.load().flags_dir.clone();
+    let store = ChangeRequestStore::new(&flags_dir);
+    let _lock = store.lock_reviews();
+
+    let mut request = store.get(&id)?;
+    tracing::Span::current().record("flag_set", request.flag_set.as_str());
+    let reviewer = reviewer(&headers, &request)?;
+
+    if let Some(schedule) = &request.schedule {
+        review_schedule(&flags_dir, schedule, ScheduleStatus::Rejected)?;
+    }
+
+    let comment = payload.and_then(|AppJson(review)| review.comment);
+    request.review(ChangeRequestStatus::Rejected, reviewer, comment);
+    store.save(&request)?;
+
+    tracing::info!(change_request = %request.id, "Rejected change request");
+
+    Ok(Json(request))
+}
+
+/// Release or reject the schedule of a change request, unless it was cancelled
+fn review_schedule(flags_dir: &str, id: &str, status: ScheduleStatus) -> AppResult<()> {
+    let store = ScheduleStore::new(flags_dir);
+    let _lock = store.lock();
+
+    let mut schedule = store.get(id)?;
+    match (schedule.status, status) {
+        (ScheduleStatus::Proposed, ScheduleStatus::Pending) => schedule.status = status,
+        (ScheduleStatus::Proposed, _) => schedule.finish(status, None),
+        // Rejecting the change request of a cancelled schedule only closes the request
+        (_, ScheduleStatus::Rejected) => return Ok(()),
+        _ => {
+            return Err(AppError::Conflict(format!(
+                "Schedule '{}' is no longer awaiting approval",
+                id
+            )))
+        }
+    }
+
+    store.save(&schedule)
+}
+
+/// The user reviewing a change request, which must still be pending
+fn reviewer(headers: &HeaderMap, request: &ChangeRequest) -> AppResult<String> {
+    if request.status != ChangeRequestStatus::Pending {
+        return Err(AppError::Conflict(format!(
+            "Change request '{}' was already {}",
+            request.id,
+            match request.status {
+                ChangeRequestStatus::Approved => "approved",
+                _ => "rejected",
+            }
+        )));
+    }
+
+    actor(headers).ok_or_else(|| {
+        AppError::Unauthorized(
+            "Reviewing change requests requires an authenticated user".to_string(),
+        )
+    })
+}
@@ -0,0 +1,105 @@
+use axum::{
+    body::Bytes,
+    extract::{rejection::BytesRejection, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    convert::{self, ConvertSource, UntranslatedRule},
+    error::{AppError, AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::flags::{create_flag_set, AppState},
+    lint::LintReport,
+};
+
+/// Query parameters of the convert endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ConvertParams {
+    /// Feature flag system the export comes from
+    #[param(inline)]
+    pub from: ConvertSource,
+    /// Name of the flag set to create
+    #[param(example = "imported")]
+    pub name: String,
+    /// Environment to convert, required when the export has several
+    pub environment: Option<String>,
+    /// Convert and validate without creating the flag set
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Response of a conversion
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConvertResponse {
+    /// Name of the flag set
+    #[schema(example = "imported")]
+    pub name: String,
+    /// The converted flag definition including $schema
+    #[schema(value_type = Object)]
+    pub content: serde_json::Value,
+    /// Result of the semantic lint pass. Only contains errors for dry runs.
+    pub lint: LintReport,
+    /// Rules and settings that were left out of the converted flags
+    pub untranslated: Vec<UntranslatedRule>,
+}
+
+/// Create a flag set from the export of another feature flag system
+///
+/// Converts flags from LaunchDarkly, Unleash, Flipt or GO Feature Flag to flagd flags
+/// with their variants, default variant and targeting. The flag set is validated like
+/// one created with `POST /api/flags`. Targeting that flagd cannot express is left out
+/// and listed in `untranslated`.
+#[utoipa::path(
+    post,
+    path = "/api/convert",
+    params(ConvertParams),
+    request_body(
+        description = "Export of the source system, JSON or YAML",
+        content(
+            (String = "application/json"),
+            (String = "application/yaml")
+        )
+    ),
+    responses(
+        (status = 201, description = "Flag set created from the export", body = ConvertResponse),
+        (status = 200, description = "Dry run completed, nothing was written", body = ConvertResponse),
+        (status = 400, description = "Malformed export (`bad_request`), invalid name (`invalid_name`) or validation of the converted flags failed (`validation_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Flag definition already exists (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 413, description = "Request body too large (`payload_too_large`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "flags"
+)]
+pub async fn convert_flags(
+    State(state): State<AppState>,
+    AppQuery(params): AppQuery<ConvertParams>,
+    body: Result<Bytes, BytesRejection>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", params.name.as_str());
+    let body = body?;
+    let input = std::str::from_utf8(&body)
+        .map_err(|_| AppError::BadRequest("The export is not UTF-8 text".to_string()))?;
+
+    let conversion = convert::convert(params.from, input, params.environment.as_deref())
+        .map_err(AppError::BadRequest)?;
+
+    let complete_doc = serde_json::json!({
+        "$schema": state.schemas.load().default_schema_url(),
+        "flags": conversion.flags
+    });
+    let (status, created) = create_flag_set(&state, params.name, complete_doc, params.dry_run)?;
+
+    Ok((
+        status,
+        Json(ConvertResponse {
+            name: created.name,
+            content: created.content,
+            lint: created.lint,
+            untranslated: conversion.untranslated,
+        }),
+    ))
+}
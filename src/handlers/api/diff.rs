@@ -0,0 +1,80 @@
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::Deserialize;
+use serde_json::Value;
+use utoipa::IntoParams;
+
+use crate::{
+    change_requests::ChangeRequestStore,
+    diff::{diff_documents, FlagSetDiff},
+    error::{AppError, AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::flags::{get_flag_file_path, read_flag_document, AppState},
+};
+
+/// Prefix of revision references naming a change request
+const CHANGE_REQUEST_PREFIX: &str = "change-request:";
+
+/// Query parameters of the diff endpoint
+///
+/// Each side is a flag set name, or a revision: `change-request:<id>` is the document
+/// a change request proposes, `change-request:<id>:base` the flag set it was proposed
+/// against.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiffParams {
+    /// Flag set or revision on the left side
+    #[param(example = "staging")]
+    pub left: String,
+    /// Flag set or revision on the right side
+    #[param(example = "prod")]
+    pub right: String,
+}
+
+/// Show a flag-aware difference between two flag sets or revisions
+#[utoipa::path(
+    get,
+    path = "/api/diff",
+    params(DiffParams),
+    responses(
+        (status = 200, description = "Changes from the left to the right side", body = FlagSetDiff),
+        (status = 400, description = "Missing parameter (`bad_request`) or invalid name (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Flag definition, change request or its base document not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "flags"
+)]
+pub async fn diff_flag_sets(
+    State(state): State<AppState>,
+    AppQuery(params): AppQuery<DiffParams>,
+) -> AppResult<impl IntoResponse> {
+    let flags_dir = state.config.load().flags_dir.clone();
+
+    let left = resolve_revision(&flags_dir, &params.left)?;
+    let right = resolve_revision(&flags_dir, &params.right)?;
+
+    Ok(Json(diff_documents(&left, &right)))
+}
+
+/// The document a flag set name or revision reference stands for
+fn resolve_revision(flags_dir: &str, reference: &str) -> AppResult<Value> {
+    let Some(revision) = reference.strip_prefix(CHANGE_REQUEST_PREFIX) else {
+        let file_path = get_flag_file_path(flags_dir, reference)?;
+        if !file_path.exists() {
+            return Err(AppError::NotFound(format!(
+                "Flag definition '{}' not found",
+                reference
+            )));
+        }
+        return read_flag_document(&file_path);
+    };
+
+    let store = ChangeRequestStore::new(flags_dir);
+    match revision.strip_suffix(":base") {
+        Some(id) => store.get(id)?.base.ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Change request '{}' has no recorded base document",
+                id
+            ))
+        }),
+        None => Ok(store.get(revision)?.content),
+    }
+}
@@ -0,0 +1,293 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    time::UNIX_EPOCH,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    archive::{self, ArchiveFile},
+    document::flag_entries,
+    error::{AppError, AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::flags::{get_flag_file_path, list_flag_set_names, AppState},
+    metrics::METRICS,
+    records::now,
+};
+
+/// Size of the chunks a streamed archive is sent in
+const ARCHIVE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Chunks of a streamed archive buffered until the client receives them
+const ARCHIVE_CHANNEL_CAPACITY: usize = 4;
+
+/// Format of the export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+pub enum ExportFormat {
+    /// Gzip compressed tar archive of all flag definition files and a manifest
+    #[default]
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    /// Zip archive of all flag definition files and a manifest
+    #[serde(rename = "zip")]
+    Zip,
+    /// A single flagd document combining the flags of all flag sets
+    #[serde(rename = "merged")]
+    Merged,
+}
+
+/// Query parameters of the export endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    /// Format of the export, `tar.gz` by default
+    #[serde(default)]
+    #[param(inline)]
+    pub format: ExportFormat,
+}
+
+/// Export all flag sets as an archive or as one merged flagd document
+#[utoipa::path(
+    get,
+    path = "/api/export",
+    params(ExportParams),
+    responses(
+        (status = 200, description = "Archive with all flag definition files and `manifest.json`, or the merged flagd document",
+            content(
+                (Vec<u8> = "application/gzip"),
+                (Vec<u8> = "application/zip"),
+                (Object = "application/json")
+            )
+        ),
+        (status = 400, description = "Unknown format (`bad_request`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Flag keys or evaluators collide between flag sets, for `format=merged` (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "flags"
+)]
+pub async fn export_flags(
+    State(state): State<AppState>,
+    AppQuery(params): AppQuery<ExportParams>,
+) -> AppResult<Response> {
+    let flags_dir = state.config.load().flags_dir.clone();
+    let names = list_flag_set_names(&flags_dir)?;
+
+    let read_all = |names: Vec<String>| {
+        names
+            .into_iter()
+            .map(|name| {
+                read_flag_set(&flags_dir, name).map_err(|e| {
+                    AppError::InternalServerError(format!("Failed to read file: {}", e))
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()
+    };
+
+    let timestamp = now();
+    let (archive, content_type, extension) = match params.format {
+        ExportFormat::Merged => {
+            let files = read_all(names)?;
+            let merged = merge_flag_sets(&files, state.schemas.load().default_schema_url())?;
+            return Ok(Json(merged).into_response());
+        }
+        ExportFormat::TarGz => (
+            stream_tar_gz(flags_dir, names),
+            "application/gzip",
+            "tar.gz",
+        ),
+        ExportFormat::Zip => {
+            let files = read_all(names)?;
+            (
+                Body::from(
+                    tokio::task::spawn_blocking(move || archive::write_zip(&files))
+                        .await
+                        .map_err(|e| AppError::InternalServerError(e.to_string()))?
+                        .map_err(|e| {
+                            AppError::InternalServerError(format!(
+                                "Failed to create archive: {}",
+                                e
+                            ))
+                        })?,
+                ),
+                "application/zip",
+                "zip",
+            )
+        }
+    };
+
+    let disposition = format!("attachment; filename=\"flags-{}.{}\"", timestamp, extension);
+    Ok((
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+/// A flag definition file with its modification time, as put into an archive
+fn read_flag_set(flags_dir: &str, name: String) -> io::Result<ArchiveFile> {
+    let file_path =
+        get_flag_file_path(flags_dir, &name).map_err(|e| io::Error::other(e.to_string()))?;
+    let read = || {
+        let content = fs::read(&file_path)?;
+        let modified_at = fs::metadata(&file_path)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Ok(ArchiveFile {
+            name,
+            content,
+            modified_at,
+        })
+    };
+
+    read().inspect_err(|_| METRICS.record_storage_error("read"))
+}
+
+/// Response body of a tar.gz archive of the named flag sets, read and compressed on
+/// a blocking thread while it is sent.
+///
+/// Neither the archive nor the flag sets are ever held in memory as a whole, each
+/// file is read when its entry is written. A failure while writing the archive ends
+/// the body with an error, so the client sees an aborted transfer rather than a
+/// truncated archive.
+fn stream_tar_gz(flags_dir: String, names: Vec<String>) -> Body {
+    let (sender, receiver) = mpsc::channel(ARCHIVE_CHANNEL_CAPACITY);
+
+    tokio::task::spawn_blocking(move || {
+        let errors = sender.clone();
+        let writer = io::BufWriter::with_capacity(ARCHIVE_CHUNK_SIZE, ChannelWriter(sender));
+        let files = names
+            .into_iter()
+            .map(|name| read_flag_set(&flags_dir, name));
+        let result = archive::write_tar_gz(files, writer).and_then(|mut writer| writer.flush());
+
+        match result {
+            Ok(()) => {}
+            // The client went away, nobody is left to tell
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                tracing::debug!("Export archive no longer received");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to create archive");
+                let _ = errors.blocking_send(Err(e));
+            }
+        }
+    });
+
+    Body::from_stream(ReceiverStream::new(receiver))
+}
+
+/// Writer passing what is written on to the channel of a streamed response body
+struct ChannelWriter(mpsc::Sender<io::Result<Bytes>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Combine the flags and evaluators of all flag sets into one flagd document.
+///
+/// Flag set metadata is copied into the metadata of its flags, as flagd does when
+/// evaluating, so the merged flags keep their `flagSetId`.
+fn merge_flag_sets(files: &[ArchiveFile], schema_url: String) -> AppResult<Value> {
+    let mut flags = Map::new();
+    let mut evaluators = Map::new();
+    let mut flag_origins = BTreeMap::new();
+    let mut evaluator_origins = BTreeMap::new();
+    let mut collisions = Vec::new();
+
+    for file in files {
+        let doc = serde_json::from_slice::<Value>(&file.content).map_err(|e| {
+            AppError::InternalServerError(format!(
+                "Failed to parse flag set '{}': {}",
+                file.name, e
+            ))
+        })?;
+        let set_metadata = doc.get("metadata").and_then(Value::as_object);
+
+        for (key, flag, _) in flag_entries(&doc) {
+            if let Some(origin) = flag_origins.insert(key.clone(), file.name.clone()) {
+                collisions.push(format!(
+                    "flag '{}' in '{}' and '{}'",
+                    key, origin, file.name
+                ));
+                continue;
+            }
+
+            let mut flag = flag.clone();
+            if let Some(flag) = flag.as_object_mut() {
+                // Array form flags carry their key, which the object form does not allow
+                flag.remove("key");
+                if let Some(set_metadata) = set_metadata {
+                    let metadata = flag
+                        .entry("metadata")
+                        .or_insert_with(|| Value::Object(Map::new()));
+                    if let Some(metadata) = metadata.as_object_mut() {
+                        for (name, value) in set_metadata {
+                            metadata.entry(name.clone()).or_insert(value.clone());
+                        }
+                    }
+                }
+            }
+            flags.insert(key, flag);
+        }
+
+        for (name, evaluator) in doc
+            .get("$evaluators")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+        {
+            match evaluators.get(name) {
+                // Shared evaluators are commonly copied between flag sets
+                Some(existing) if existing == evaluator => {}
+                Some(_) => collisions.push(format!(
+                    "evaluator '{}' in '{}' and '{}'",
+                    name, evaluator_origins[name], file.name
+                )),
+                None => {
+                    evaluators.insert(name.clone(), evaluator.clone());
+                    evaluator_origins.insert(name.clone(), file.name.clone());
+                }
+            }
+        }
+    }
+
+    if !collisions.is_empty() {
+        return Err(AppError::Conflict(format!(
+            "Flag sets cannot be merged, keys collide: {}",
+            collisions.join(", ")
+        )));
+    }
+
+    let mut merged = Map::new();
+    merged.insert("$schema".to_string(), Value::String(schema_url));
+    merged.insert("flags".to_string(), Value::Object(flags));
+    if !evaluators.is_empty() {
+        merged.insert("$evaluators".to_string(), Value::Object(evaluators));
+    }
+    Ok(Value::Object(merged))
+}
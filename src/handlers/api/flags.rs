@@ -1,60 +1,69 @@
+use arc_swap::ArcSwap;
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{
+        header::{ETAG, IF_MATCH},
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+    },
     response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf, sync::Arc};
-use utoipa::ToSchema;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path as FsPath, PathBuf},
+    sync::Arc,
+    time::{Instant, UNIX_EPOCH},
+};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::{
+    background::BackgroundTasks,
+    change_requests::{is_protected, ChangeRequest, ChangeRequestStatus, ChangeRequestStore},
     config::ServerConfig,
-    error::{AppError, AppResult},
+    document::flag_entries,
+    error::{AppError, AppJson, AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::change_requests::propose_change,
+    lifecycle::{clear_lifecycle, stamp_lifecycle},
+    lint::{self, LintReport},
+    metrics::METRICS,
+    rollouts::RolloutStore,
+    schedules::{ScheduleStatus, ScheduleStore},
+    schema::SchemaRegistry,
+    search::SearchIndex,
 };
 
-struct LocalSchemaRetriever {
-    base_dir: PathBuf,
-}
-
-impl jsonschema::Retrieve for LocalSchemaRetriever {
-    fn retrieve(
-        &self,
-        uri: &jsonschema::Uri<&str>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
-        let uri_str = uri.as_str();
-
-        let candidate = if uri_str.starts_with("json-schema:///") {
-            let rel = uri_str.trim_start_matches("json-schema:///");
-            self.base_dir.join(rel)
-        } else if uri.scheme().as_str() == "file" {
-            PathBuf::from(uri.path().as_str())
-        } else if uri.scheme().as_str() == "https"
-            && uri.path().as_str().ends_with("targeting.json")
-        {
-            self.base_dir.join("targeting.json")
-        } else {
-            return Err(format!("Unsupported schema URI: {uri_str}").into());
-        };
-
-        let content = fs::read_to_string(&candidate)?;
-        Ok(serde_json::from_str(&content)?)
-    }
-}
-
-/// Application state containing configuration
+/// Application state containing configuration.
+///
+/// Configuration and schemas can be swapped at runtime, see `handlers::admin`. They
+/// are swapped separately, so a request that loads both may briefly see a new one
+/// with the old other.
 #[derive(Clone)]
 pub struct AppState {
-    pub config: Arc<ServerConfig>,
-    pub schema: Arc<jsonschema::Validator>,
+    pub config: Arc<ArcSwap<ServerConfig>>,
+    pub schemas: Arc<ArcSwap<SchemaRegistry>>,
+    /// Background tasks whose health is reported by `/ready`
+    pub tasks: BackgroundTasks,
+    /// When the server was started, reported by `/health`
+    pub started_at: Instant,
+    /// Index of the searchable text of all flag sets
+    pub search: SearchIndex,
 }
 
+/// Largest page size of the flag set listing
+const MAX_LIST_LIMIT: usize = 1000;
+
 /// Request payload for creating a new flag definition file
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateFlagRequest {
     /// Name of the flag definition file (without .flagd.json extension)
     #[schema(example = "my-flags")]
     pub name: String,
+    /// flagd schema URL of the flag set, defaults to the latest supported version
+    #[serde(rename = "$schema")]
+    #[schema(example = "https://flagd.dev/schema/v0/flags.json")]
+    pub schema: Option<String>,
     /// Flag definitions (without $schema property)
     #[schema(value_type = Object)]
     pub flags: serde_json::Value,
@@ -66,6 +75,10 @@ pub struct CreateFlagRequest {
 /// Request payload for updating a flag definition file
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateFlagRequest {
+    /// flagd schema URL of the flag set, defaults to the one already stored
+    #[serde(rename = "$schema")]
+    #[schema(example = "https://flagd.dev/schema/v0/flags.json")]
+    pub schema: Option<String>,
     /// Flag definitions (without $schema property)
     #[schema(value_type = Object)]
     pub flags: serde_json::Value,
@@ -74,6 +87,17 @@ pub struct UpdateFlagRequest {
     pub metadata: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
+/// Query parameters shared by the write endpoints
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct WriteParams {
+    /// Validate and lint the document without writing it. Lint errors are
+    /// reported in the response instead of rejecting the request.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
 /// Response for a single flag definition file
 #[derive(Debug, Serialize, ToSchema)]
 pub struct FlagDefinitionResponse {
@@ -83,82 +107,271 @@ pub struct FlagDefinitionResponse {
     /// Complete flag definition content including $schema
     #[schema(value_type = Object)]
     pub content: serde_json::Value,
+    /// Result of the semantic lint pass. Only contains errors for dry runs.
+    pub lint: LintReport,
 }
 
 /// Response for listing all flag definition files
 #[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct ListFlagsResponse {
     /// List of flag definition file names
     #[schema(example = json!(["demo", "production"]))]
     pub files: Vec<String>,
+    /// Cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "production")]
+    pub next_cursor: Option<String>,
+    /// Summaries of the listed flag sets, with `include=summary`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summaries: Option<Vec<FlagSetSummary>>,
+}
+
+/// Query parameters for listing flag definition files
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListFlagsParams {
+    /// Only list flag sets whose name starts with this prefix
+    #[param(example = "team-")]
+    pub prefix: Option<String>,
+    /// Continue after the page that returned this `nextCursor`
+    pub cursor: Option<String>,
+    /// Maximum number of flag sets to return, between 1 and 1000; all by default
+    #[param(example = 50)]
+    pub limit: Option<usize>,
+    /// Comma separated extra data to include, currently only `summary`
+    #[param(example = "summary")]
+    pub include: Option<String>,
+}
+
+/// Overview of a flag set, returned by the listing with `include=summary`
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FlagSetSummary {
+    /// Name of the flag definition file
+    #[schema(example = "demo")]
+    pub name: String,
+    /// Number of flags
+    pub flag_count: usize,
+    /// Number of flags in state `ENABLED`
+    pub enabled_count: usize,
+    /// Number of flags in state `DISABLED`
+    pub disabled_count: usize,
+    /// `metadata.flagSetId` of the flag set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flag_set_id: Option<String>,
+    /// `metadata.version` of the flag set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Unix timestamp in seconds of the last modification of the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<u64>,
+    /// Size of the file in bytes
+    pub size: u64,
+    pub validation: ValidationStatus,
+}
+
+/// Result of validating a stored flag set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationStatus {
+    /// Passes schema validation and linting without findings
+    Valid,
+    /// Passes schema validation with lint warnings only
+    Warnings,
+    /// Is not valid JSON, fails schema validation or has lint errors
+    Invalid,
+}
+
+/// Request payload for renaming a flag definition file
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameFlagRequest {
+    /// New name of the flag definition file
+    #[schema(example = "production")]
+    pub new_name: String,
+}
+
+/// Request payload for copying a flag definition file
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFlagRequest {
+    /// Name of the new flag definition file
+    #[schema(example = "production")]
+    pub new_name: String,
+    /// Value for `metadata.flagSetId` of the copy, kept from the source if omitted
+    #[schema(example = "production")]
+    pub flag_set_id: Option<String>,
 }
 
 /// Initialize the application state with schema validation
 pub async fn init_app_state(config: ServerConfig) -> AppResult<AppState> {
-    let schema_dir = PathBuf::from(&config.schema_file_path)
-        .parent()
-        .map(|p| p.to_path_buf())
-        .unwrap_or_else(|| PathBuf::from("."));
-
-    // Load the schema from the local file
-    let schema_content = fs::read_to_string(&config.schema_file_path)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to read schema file: {}", e)))?;
-
-    let schema_json: serde_json::Value = serde_json::from_str(&schema_content)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to parse schema: {}", e)))?;
-
-    // Compile the JSON schema into a validator
-    let schema = jsonschema::options()
-        .with_retriever(LocalSchemaRetriever {
-            base_dir: schema_dir,
-        })
-        .build(&schema_json)
-        .map_err(|e| AppError::InternalServerError(format!("Invalid schema: {}", e)))?;
+    let schemas = SchemaRegistry::load(config.schema_file_path.as_deref())?;
 
-    Ok(AppState {
-        config: Arc::new(config),
-        schema: Arc::new(schema),
-    })
+    let state = AppState {
+        config: Arc::new(ArcSwap::from_pointee(config)),
+        schemas: Arc::new(ArcSwap::from_pointee(schemas)),
+        tasks: BackgroundTasks::default(),
+        started_at: Instant::now(),
+        search: SearchIndex::default(),
+    };
+    rebuild_search_index(&state);
+
+    Ok(state)
+}
+
+/// Index the flag set files from scratch, picking up edits made outside the API
+pub(crate) fn rebuild_search_index(state: &AppState) {
+    let flags_dir = state.config.load().flags_dir.clone();
+    match list_flag_set_names(&flags_dir) {
+        Ok(names) => state.search.rebuild(FsPath::new(&flags_dir), &names),
+        Err(error) => tracing::warn!(error = %error, "Failed to build the search index"),
+    }
 }
 
-/// Validate flag definition against the schema
-fn validate_flags(
-    schema: &jsonschema::Validator,
+/// Validate flag definition against the schema version it declares
+#[tracing::instrument(skip_all, fields(schema_version))]
+pub(crate) fn validate_flags(
+    schemas: &SchemaRegistry,
     complete_doc: &serde_json::Value,
 ) -> AppResult<()> {
+    let (version, schema) = schemas.validator_for(complete_doc)?;
+    tracing::Span::current().record("schema_version", version);
+
     schema
         .validate(complete_doc)
-        .map_err(|error| AppError::BadRequest(format!("Schema validation failed: {}", error)))
+        .map_err(|error| AppError::ValidationFailed(format!("Schema validation failed: {}", error)))
+}
+
+/// Run the semantic lint pass on a document that already passed schema validation.
+///
+/// Lint errors reject the document unless this is a dry run, in which case they are
+/// returned to the caller as part of the report.
+#[tracing::instrument(skip_all)]
+pub(crate) fn lint_flags(complete_doc: &serde_json::Value, dry_run: bool) -> AppResult<LintReport> {
+    let report = lint::lint_document(complete_doc);
+
+    if report.has_errors() && !dry_run {
+        return Err(AppError::ValidationFailed(format!(
+            "Semantic validation failed: {}",
+            report.error_summary()
+        )));
+    }
+
+    Ok(report)
 }
 
 /// Build the file path for a flag definition file
-fn get_flag_file_path(flags_dir: &str, name: &str) -> AppResult<PathBuf> {
+pub(crate) fn get_flag_file_path(flags_dir: &str, name: &str) -> AppResult<PathBuf> {
     // Validate filename to prevent path traversal attacks
     if name.contains("..") || name.contains('/') || name.contains('\\') {
-        return Err(AppError::BadRequest(
+        return Err(AppError::InvalidName(
             "Invalid filename: cannot contain path separators or '..'".to_string(),
         ));
     }
 
     if name.is_empty() {
-        return Err(AppError::BadRequest("Filename cannot be empty".to_string()));
+        return Err(AppError::InvalidName(
+            "Filename cannot be empty".to_string(),
+        ));
     }
 
     Ok(PathBuf::from(flags_dir).join(format!("{}.flagd.json", name)))
 }
 
-/// List all flag definition files
-#[utoipa::path(
-    get,
-    path = "/api/flags",
-    responses(
-        (status = 200, description = "List of all flag definition files", body = ListFlagsResponse),
-        (status = 500, description = "Internal server error")
-    ),
-    tag = "flags"
-)]
-pub async fn list_flags(State(state): State<AppState>) -> AppResult<impl IntoResponse> {
-    let flags_dir = PathBuf::from(&state.config.flags_dir);
+/// Strong entity tag of a flag set document, the SHA-256 of its serialized form
+pub(crate) fn document_etag(doc: &serde_json::Value) -> String {
+    let digest = Sha256::digest(doc.to_string().as_bytes());
+    let hex = digest
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    format!("\"{}\"", hex)
+}
+
+/// Reject the request unless its `If-Match` header, if any, matches the current document
+pub(crate) fn check_if_match(
+    headers: &HeaderMap,
+    name: &str,
+    current_doc: &serde_json::Value,
+) -> AppResult<()> {
+    let Some(if_match) = headers.get(IF_MATCH) else {
+        return Ok(());
+    };
+
+    let etag = document_etag(current_doc);
+    let matches = if_match.to_str().is_ok_and(|value| {
+        value
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag == etag)
+    });
+
+    if !matches {
+        return Err(AppError::PreconditionFailed(format!(
+            "Flag definition '{}' was modified, If-Match does not match its current ETag",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+/// Reject operations on a protected flag set that cannot go through a change request
+fn ensure_unprotected(
+    config: &ServerConfig,
+    name: &str,
+    doc: &serde_json::Value,
+    action: &str,
+) -> AppResult<()> {
+    if is_protected(config, name, doc) {
+        return Err(AppError::Forbidden(format!(
+            "Flag set '{}' is protected and cannot be {}",
+            name, action
+        )));
+    }
+
+    Ok(())
+}
+
+/// `ETag` header of a flag set document
+pub(crate) fn etag_header(doc: &serde_json::Value) -> [(HeaderName, HeaderValue); 1] {
+    let etag = HeaderValue::from_str(&document_etag(doc)).expect("hex digest is a valid header");
+    [(ETAG, etag)]
+}
+
+/// Read and parse a flag definition file
+#[tracing::instrument(skip_all, fields(path = %file_path.display()))]
+pub(crate) fn read_flag_document(file_path: &FsPath) -> AppResult<serde_json::Value> {
+    let content = fs::read_to_string(file_path).map_err(|e| {
+        METRICS.record_storage_error("read");
+        AppError::InternalServerError(format!("Failed to read file: {}", e))
+    })?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to parse JSON: {}", e)))
+}
+
+/// Serialize and write a flag definition file
+#[tracing::instrument(skip_all, fields(path = %file_path.display()))]
+pub(crate) fn write_flag_document(
+    file_path: &FsPath,
+    complete_doc: &serde_json::Value,
+) -> AppResult<()> {
+    let json_string = serde_json::to_string_pretty(complete_doc)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to serialize JSON: {}", e)))?;
+
+    fs::write(file_path, json_string).map_err(|e| {
+        METRICS.record_storage_error("write");
+        AppError::InternalServerError(format!("Failed to write file: {}", e))
+    })
+}
+
+/// List the names of all flag definition files in the flags directory, sorted
+#[tracing::instrument]
+pub(crate) fn list_flag_set_names(flags_dir: &str) -> AppResult<Vec<String>> {
+    let flags_dir = PathBuf::from(flags_dir);
 
     // Create directory if it doesn't exist
     if !flags_dir.exists() {
@@ -168,6 +381,7 @@ pub async fn list_flags(State(state): State<AppState>) -> AppResult<impl IntoRes
     }
 
     let entries = fs::read_dir(&flags_dir).map_err(|e| {
+        METRICS.record_storage_error("list");
         AppError::InternalServerError(format!("Failed to read flags directory: {}", e))
     })?;
 
@@ -193,7 +407,151 @@ pub async fn list_flags(State(state): State<AppState>) -> AppResult<impl IntoRes
 
     files.sort();
 
-    Ok(Json(ListFlagsResponse { files }))
+    Ok(files)
+}
+
+/// List flag definition files
+#[utoipa::path(
+    get,
+    path = "/api/flags",
+    params(ListFlagsParams),
+    responses(
+        (status = 200, description = "Flag definition files, sorted by name", body = ListFlagsResponse),
+        (status = 400, description = "Invalid query parameters (`bad_request`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "flags"
+)]
+pub async fn list_flags(
+    State(state): State<AppState>,
+    AppQuery(params): AppQuery<ListFlagsParams>,
+) -> AppResult<impl IntoResponse> {
+    if params
+        .limit
+        .is_some_and(|limit| !(1..=MAX_LIST_LIMIT).contains(&limit))
+    {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_LIST_LIMIT
+        )));
+    }
+
+    let mut include_summary = false;
+    for include in params.include.iter().flat_map(|include| include.split(',')) {
+        match include.trim() {
+            "summary" => include_summary = true,
+            "" => {}
+            other => {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown include '{}', expected 'summary'",
+                    other
+                )))
+            }
+        }
+    }
+
+    let flags_dir = state.config.load().flags_dir.clone();
+    let mut files = list_flag_set_names(&flags_dir)?
+        .into_iter()
+        .filter(|name| {
+            params
+                .prefix
+                .as_ref()
+                .is_none_or(|prefix| name.starts_with(prefix.as_str()))
+        })
+        // Names are sorted, so the cursor is the last name of the previous page
+        .filter(|name| {
+            params
+                .cursor
+                .as_ref()
+                .is_none_or(|cursor| name.as_str() > cursor.as_str())
+        })
+        .collect::<Vec<_>>();
+
+    let mut next_cursor = None;
+    if let Some(limit) = params.limit {
+        if files.len() > limit {
+            files.truncate(limit);
+            next_cursor = files.last().cloned();
+        }
+    }
+
+    let summaries = if include_summary {
+        let schemas = state.schemas.load();
+        let summaries = files
+            .iter()
+            .map(|name| summarize_flag_set(&schemas, &get_flag_file_path(&flags_dir, name)?, name))
+            .collect::<AppResult<Vec<_>>>()?;
+        Some(summaries)
+    } else {
+        None
+    };
+
+    Ok(Json(ListFlagsResponse {
+        files,
+        next_cursor,
+        summaries,
+    }))
+}
+
+/// Summarize a stored flag set, reporting broken files instead of failing
+fn summarize_flag_set(
+    schemas: &SchemaRegistry,
+    file_path: &FsPath,
+    name: &str,
+) -> AppResult<FlagSetSummary> {
+    let file_metadata = fs::metadata(file_path).map_err(|e| {
+        METRICS.record_storage_error("read");
+        AppError::InternalServerError(format!("Failed to read file metadata: {}", e))
+    })?;
+    let modified_at = file_metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs());
+
+    let doc = fs::read_to_string(file_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+    let doc = doc.unwrap_or(serde_json::Value::Null);
+
+    let flags = flag_entries(&doc);
+    let count_state = |state: &str| {
+        flags
+            .iter()
+            .filter(|(_, flag, _)| flag.get("state").and_then(|s| s.as_str()) == Some(state))
+            .count()
+    };
+    let metadata = |key: &str| {
+        doc.pointer(&format!("/metadata/{}", key))
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    };
+
+    let validation = if doc.is_null() || validate_flags(schemas, &doc).is_err() {
+        ValidationStatus::Invalid
+    } else {
+        let report = lint::lint_document(&doc);
+        if report.has_errors() {
+            ValidationStatus::Invalid
+        } else if !report.warnings.is_empty() {
+            ValidationStatus::Warnings
+        } else {
+            ValidationStatus::Valid
+        }
+    };
+
+    Ok(FlagSetSummary {
+        name: name.to_string(),
+        flag_count: flags.len(),
+        enabled_count: count_state("ENABLED"),
+        disabled_count: count_state("DISABLED"),
+        flag_set_id: metadata("flagSetId"),
+        version: metadata("version"),
+        modified_at,
+        size: file_metadata.len(),
+        validation,
+    })
 }
 
 /// Get a specific flag definition file
@@ -204,10 +562,10 @@ pub async fn list_flags(State(state): State<AppState>) -> AppResult<impl IntoRes
         ("name" = String, Path, description = "Name of the flag definition file")
     ),
     responses(
-        (status = 200, description = "Flag definition file content", body = Object),
-        (status = 404, description = "Flag definition not found"),
-        (status = 400, description = "Invalid filename"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Flag definition file content, its version in the `ETag` header", body = Object),
+        (status = 404, description = "Flag definition not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 400, description = "Invalid filename (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
     ),
     tag = "flags"
 )]
@@ -215,7 +573,8 @@ pub async fn get_flag(
     State(state): State<AppState>,
     Path(name): Path<String>,
 ) -> AppResult<impl IntoResponse> {
-    let file_path = get_flag_file_path(&state.config.flags_dir, &name)?;
+    tracing::Span::current().record("flag_set", name.as_str());
+    let file_path = get_flag_file_path(&state.config.load().flags_dir, &name)?;
 
     if !file_path.exists() {
         return Err(AppError::NotFound(format!(
@@ -224,43 +583,41 @@ pub async fn get_flag(
         )));
     }
 
-    let content = fs::read_to_string(&file_path)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to read file: {}", e)))?;
+    let json = read_flag_document(&file_path)?;
 
-    let json: serde_json::Value = serde_json::from_str(&content)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to parse JSON: {}", e)))?;
-
-    Ok(Json(json))
+    Ok((etag_header(&json), Json(json)))
 }
 
 /// Create a new flag definition file
 #[utoipa::path(
     post,
     path = "/api/flags",
+    params(WriteParams),
     request_body = CreateFlagRequest,
     responses(
         (status = 201, description = "Flag definition file created successfully", body = FlagDefinitionResponse),
-        (status = 400, description = "Invalid request or validation failed"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Dry run completed, nothing was written", body = FlagDefinitionResponse),
+        (status = 400, description = "Malformed request (`bad_request`), invalid name (`invalid_name`) or validation failed (`validation_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 413, description = "Request body too large (`payload_too_large`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 415, description = "Request body is not JSON (`unsupported_media_type`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Flag definition already exists (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
     ),
     tag = "flags"
 )]
 pub async fn create_flag(
     State(state): State<AppState>,
-    Json(payload): Json<CreateFlagRequest>,
+    AppQuery(params): AppQuery<WriteParams>,
+    AppJson(payload): AppJson<CreateFlagRequest>,
 ) -> AppResult<impl IntoResponse> {
-    let file_path = get_flag_file_path(&state.config.flags_dir, &payload.name)?;
+    tracing::Span::current().record("flag_set", payload.name.as_str());
 
-    // Check if file already exists
-    if file_path.exists() {
-        return Err(AppError::BadRequest(format!(
-            "Flag definition '{}' already exists",
-            payload.name
-        )));
-    }
+    let schema_url = payload
+        .schema
+        .unwrap_or_else(|| state.schemas.load().default_schema_url());
 
     let mut complete_doc = serde_json::json!({
-        "$schema": "https://flagd.dev/schema/v0/flags.json",
+        "$schema": schema_url,
         "flags": payload.flags
     });
 
@@ -268,8 +625,45 @@ pub async fn create_flag(
         complete_doc["metadata"] = serde_json::Value::Object(metadata);
     }
 
-    // Validate the full document against the schema
-    validate_flags(&state.schema, &complete_doc)?;
+    let (status, response) = create_flag_set(&state, payload.name, complete_doc, params.dry_run)?;
+    Ok((status, Json(response)))
+}
+
+/// Validate and write a new flag set, or only validate it on a dry run
+pub(crate) fn create_flag_set(
+    state: &AppState,
+    name: String,
+    mut complete_doc: serde_json::Value,
+    dry_run: bool,
+) -> AppResult<(StatusCode, FlagDefinitionResponse)> {
+    let file_path = get_flag_file_path(&state.config.load().flags_dir, &name)?;
+
+    // Check if file already exists
+    if file_path.exists() {
+        return Err(AppError::Conflict(format!(
+            "Flag definition '{}' already exists",
+            name
+        )));
+    }
+
+    stamp_lifecycle(None, &mut complete_doc);
+
+    // Validate the full document against the schema and the semantic rules
+    validate_flags(&state.schemas.load(), &complete_doc)
+        .inspect_err(|_| METRICS.record_validation_failure(&name, "schema"))?;
+    let lint = lint_flags(&complete_doc, dry_run)
+        .inspect_err(|_| METRICS.record_validation_failure(&name, "semantic"))?;
+
+    if dry_run {
+        return Ok((
+            StatusCode::OK,
+            FlagDefinitionResponse {
+                name,
+                content: complete_doc,
+                lint,
+            },
+        ));
+    }
 
     // Create the directory if it doesn't exist
     if let Some(parent) = file_path.parent() {
@@ -279,18 +673,16 @@ pub async fn create_flag(
     }
 
     // Write the file
-    let json_string = serde_json::to_string_pretty(&complete_doc)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to serialize JSON: {}", e)))?;
-
-    fs::write(&file_path, json_string)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to write file: {}", e)))?;
+    write_flag_document(&file_path, &complete_doc)?;
+    state.search.update(&name, &complete_doc);
 
     Ok((
         StatusCode::CREATED,
-        Json(FlagDefinitionResponse {
-            name: payload.name,
+        FlagDefinitionResponse {
+            name,
             content: complete_doc,
-        }),
+            lint,
+        },
     ))
 }
 
@@ -299,23 +691,33 @@ pub async fn create_flag(
     put,
     path = "/api/flags/{name}",
     params(
-        ("name" = String, Path, description = "Name of the flag definition file to update")
+        ("name" = String, Path, description = "Name of the flag definition file to update"),
+        ("If-Match" = Option<String>, Header, description = "Only update if the flag set still has this `ETag`"),
+        WriteParams
     ),
     request_body = UpdateFlagRequest,
     responses(
         (status = 200, description = "Flag definition file updated successfully", body = FlagDefinitionResponse),
-        (status = 404, description = "Flag definition not found"),
-        (status = 400, description = "Invalid request or validation failed"),
-        (status = 500, description = "Internal server error")
+        (status = 202, description = "Flag set is protected, a pending change request was created", body = ChangeRequest),
+        (status = 401, description = "Flag set is protected and no authenticated user is known (`unauthorized`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Flag definition not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 400, description = "Malformed request (`bad_request`), invalid name (`invalid_name`) or validation failed (`validation_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 413, description = "Request body too large (`payload_too_large`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 415, description = "Request body is not JSON (`unsupported_media_type`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 412, description = "`If-Match` does not match the current `ETag` (`precondition_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
     ),
     tag = "flags"
 )]
 pub async fn update_flag(
     State(state): State<AppState>,
     Path(name): Path<String>,
-    Json(payload): Json<UpdateFlagRequest>,
+    headers: HeaderMap,
+    AppQuery(params): AppQuery<WriteParams>,
+    AppJson(payload): AppJson<UpdateFlagRequest>,
 ) -> AppResult<impl IntoResponse> {
-    let file_path = get_flag_file_path(&state.config.flags_dir, &name)?;
+    tracing::Span::current().record("flag_set", name.as_str());
+    let file_path = get_flag_file_path(&state.config.load().flags_dir, &name)?;
 
     // Check if file exists
     if !file_path.exists() {
@@ -326,11 +728,8 @@ pub async fn update_flag(
     }
 
     // Preserve existing metadata if the client does not send it.
-    let existing_content = fs::read_to_string(&file_path)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to read file: {}", e)))?;
-
-    let existing_json: serde_json::Value = serde_json::from_str(&existing_content)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to parse JSON: {}", e)))?;
+    let existing_json = read_flag_document(&file_path)?;
+    check_if_match(&headers, &name, &existing_json)?;
 
     let existing_metadata = existing_json
         .get("metadata")
@@ -339,8 +738,19 @@ pub async fn update_flag(
 
     let metadata_to_write = payload.metadata.or(existing_metadata);
 
+    // Keep the schema version recorded in the flag set unless the client changes it
+    let schema_url = payload
+        .schema
+        .or_else(|| {
+            existing_json
+                .get("$schema")
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| state.schemas.load().default_schema_url());
+
     let mut complete_doc = serde_json::json!({
-        "$schema": "https://flagd.dev/schema/v0/flags.json",
+        "$schema": schema_url,
         "flags": payload.flags
     });
 
@@ -348,20 +758,41 @@ pub async fn update_flag(
         complete_doc["metadata"] = serde_json::Value::Object(metadata);
     }
 
-    // Validate the full document against the schema
-    validate_flags(&state.schema, &complete_doc)?;
+    stamp_lifecycle(Some(&existing_json), &mut complete_doc);
 
-    // Write the file
-    let json_string = serde_json::to_string_pretty(&complete_doc)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to serialize JSON: {}", e)))?;
+    // Validate the full document against the schema and the semantic rules
+    validate_flags(&state.schemas.load(), &complete_doc)
+        .inspect_err(|_| METRICS.record_validation_failure(&name, "schema"))?;
+    let lint = lint_flags(&complete_doc, params.dry_run)
+        .inspect_err(|_| METRICS.record_validation_failure(&name, "semantic"))?;
 
-    fs::write(&file_path, json_string)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to write file: {}", e)))?;
+    if params.dry_run {
+        return Ok(Json(FlagDefinitionResponse {
+            name,
+            content: complete_doc,
+            lint,
+        })
+        .into_response());
+    }
 
-    Ok(Json(FlagDefinitionResponse {
-        name,
-        content: complete_doc,
-    }))
+    // Changes to protected flag sets wait for the approval of another user
+    if is_protected(&state.config.load(), &name, &existing_json) {
+        return propose_change(&state, &headers, &name, &existing_json, complete_doc);
+    }
+
+    // Write the file
+    write_flag_document(&file_path, &complete_doc)?;
+    state.search.update(&name, &complete_doc);
+
+    Ok((
+        etag_header(&complete_doc),
+        Json(FlagDefinitionResponse {
+            name,
+            content: complete_doc,
+            lint,
+        }),
+    )
+        .into_response())
 }
 
 /// Delete a flag definition file
@@ -369,21 +800,26 @@ pub async fn update_flag(
     delete,
     path = "/api/flags/{name}",
     params(
-        ("name" = String, Path, description = "Name of the flag definition file to delete")
+        ("name" = String, Path, description = "Name of the flag definition file to delete"),
+        ("If-Match" = Option<String>, Header, description = "Only delete if the flag set still has this `ETag`")
     ),
     responses(
         (status = 204, description = "Flag definition file deleted successfully"),
-        (status = 404, description = "Flag definition not found"),
-        (status = 400, description = "Invalid filename"),
-        (status = 500, description = "Internal server error")
+        (status = 403, description = "Flag set is protected (`forbidden`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 412, description = "`If-Match` does not match the current `ETag` (`precondition_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Flag definition not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 400, description = "Invalid filename (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
     ),
     tag = "flags"
 )]
 pub async fn delete_flag(
     State(state): State<AppState>,
     Path(name): Path<String>,
+    headers: HeaderMap,
 ) -> AppResult<impl IntoResponse> {
-    let file_path = get_flag_file_path(&state.config.flags_dir, &name)?;
+    tracing::Span::current().record("flag_set", name.as_str());
+    let file_path = get_flag_file_path(&state.config.load().flags_dir, &name)?;
 
     // Check if file exists
     if !file_path.exists() {
@@ -393,9 +829,264 @@ pub async fn delete_flag(
         )));
     }
 
+    let doc = read_flag_document(&file_path)?;
+    check_if_match(&headers, &name, &doc)?;
+    ensure_unprotected(&state.config.load(), &name, &doc, "deleted")?;
+
     // Delete the file
-    fs::remove_file(&file_path)
-        .map_err(|e| AppError::InternalServerError(format!("Failed to delete file: {}", e)))?;
+    let _span = tracing::info_span!("delete_flag_document", path = %file_path.display()).entered();
+    fs::remove_file(&file_path).map_err(|e| {
+        METRICS.record_storage_error("delete");
+        AppError::InternalServerError(format!("Failed to delete file: {}", e))
+    })?;
+    state.search.remove(&name);
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Link `file_path` to the new name `new_file_path`, failing instead of replacing a
+/// flag set that was created since the name was checked
+fn link_new_flag_file(file_path: &FsPath, new_file_path: &FsPath, new_name: &str) -> AppResult<()> {
+    fs::hard_link(file_path, new_file_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            AppError::Conflict(format!("Flag definition '{}' already exists", new_name))
+        }
+        _ => {
+            METRICS.record_storage_error("link");
+            AppError::InternalServerError(format!("Failed to create file: {}", e))
+        }
+    })
+}
+
+/// Return the path of a new flag definition file, rejecting names that are taken
+fn get_new_flag_file_path(flags_dir: &str, name: &str) -> AppResult<PathBuf> {
+    let file_path = get_flag_file_path(flags_dir, name)?;
+
+    if file_path.exists() {
+        return Err(AppError::Conflict(format!(
+            "Flag definition '{}' already exists",
+            name
+        )));
+    }
+
+    Ok(file_path)
+}
+
+/// Reject renaming a flag set while records that refer to it by name are still open
+fn ensure_no_open_records(
+    flags_dir: &str,
+    name: &str,
+    schedules: &ScheduleStore,
+    rollouts: &RolloutStore,
+) -> AppResult<()> {
+    let conflict = |record: &str, id: &str| {
+        Err(AppError::Conflict(format!(
+            "Flag set '{}' has {} '{}', finish or cancel it before renaming",
+            name, record, id
+        )))
+    };
+
+    if let Some(schedule) = schedules.list()?.into_iter().find(|schedule| {
+        schedule.flag_set == name
+            && matches!(
+                schedule.status,
+                ScheduleStatus::Proposed | ScheduleStatus::Pending
+            )
+    }) {
+        return conflict("the pending schedule", &schedule.id);
+    }
+
+    if let Some(rollout) = rollouts
+        .list()?
+        .into_iter()
+        .find(|rollout| rollout.flag_set == name && rollout.is_running())
+    {
+        return conflict("the running rollout", &rollout.id);
+    }
+
+    if let Some(request) = ChangeRequestStore::new(flags_dir)
+        .list()?
+        .into_iter()
+        .find(|request| request.flag_set == name && request.status == ChangeRequestStatus::Pending)
+    {
+        return conflict("the pending change request", &request.id);
+    }
+
+    Ok(())
+}
+
+/// Rename a flag definition file
+#[utoipa::path(
+    post,
+    path = "/api/flags/{name}/rename",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file to rename"),
+        ("If-Match" = Option<String>, Header, description = "Only rename if the flag set still has this `ETag`")
+    ),
+    request_body = RenameFlagRequest,
+    responses(
+        (status = 200, description = "Flag definition file renamed", body = FlagDefinitionResponse),
+        (status = 403, description = "Flag set is protected (`forbidden`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 400, description = "Malformed request (`bad_request`) or invalid name (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Flag definition not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "The new name is already taken, or a pending schedule, running rollout or pending change request refers to the flag set (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 412, description = "`If-Match` does not match the current `ETag` (`precondition_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "flags"
+)]
+pub async fn rename_flag(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    AppJson(payload): AppJson<RenameFlagRequest>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let flags_dir = state.config.load().flags_dir.clone();
+    let file_path = get_flag_file_path(&flags_dir, &name)?;
+
+    if !file_path.exists() {
+        return Err(AppError::NotFound(format!(
+            "Flag definition '{}' not found",
+            name
+        )));
+    }
+
+    let new_file_path = get_new_flag_file_path(&flags_dir, &payload.new_name)?;
+
+    let doc = read_flag_document(&file_path)?;
+    check_if_match(&headers, &name, &doc)?;
+    ensure_unprotected(&state.config.load(), &name, &doc, "renamed")?;
+
+    // Schedules and rollouts refer to the flag set by name, the locks keep new ones
+    // from starting until it is renamed
+    let schedules = ScheduleStore::new(&flags_dir);
+    let _schedules_lock = schedules.lock();
+    let rollouts = RolloutStore::new(&flags_dir);
+    let _rollouts_lock = rollouts.lock();
+    ensure_no_open_records(&flags_dir, &name, &schedules, &rollouts)?;
+
+    // Linking the new name first claims it atomically, and the flag set is never missing
+    let _span = tracing::info_span!(
+        "rename_flag_document",
+        from = %file_path.display(),
+        to = %new_file_path.display()
+    )
+    .entered();
+    link_new_flag_file(&file_path, &new_file_path, &payload.new_name)?;
+    if let Err(e) = fs::remove_file(&file_path) {
+        METRICS.record_storage_error("rename");
+        let _ = fs::remove_file(&new_file_path);
+        return Err(AppError::InternalServerError(format!(
+            "Failed to rename file: {}",
+            e
+        )));
+    }
+    state.search.rename(&name, &payload.new_name);
+
+    Ok((
+        etag_header(&doc),
+        Json(FlagDefinitionResponse {
+            name: payload.new_name,
+            lint: lint::lint_document(&doc),
+            content: doc,
+        }),
+    ))
+}
+
+/// Copy a flag definition file to a new name
+#[utoipa::path(
+    post,
+    path = "/api/flags/{name}/copy",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file to copy")
+    ),
+    request_body = CopyFlagRequest,
+    responses(
+        (status = 201, description = "Flag definition file copied", body = FlagDefinitionResponse),
+        (status = 400, description = "Malformed request (`bad_request`), invalid name (`invalid_name`) or the copy fails validation (`validation_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Flag definition not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "The new name is already taken (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "flags"
+)]
+pub async fn copy_flag(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    AppJson(payload): AppJson<CopyFlagRequest>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let flags_dir = state.config.load().flags_dir.clone();
+    let file_path = get_flag_file_path(&flags_dir, &name)?;
+
+    if !file_path.exists() {
+        return Err(AppError::NotFound(format!(
+            "Flag definition '{}' not found",
+            name
+        )));
+    }
+
+    let new_file_path = get_new_flag_file_path(&flags_dir, &payload.new_name)?;
+
+    let mut complete_doc = read_flag_document(&file_path)?;
+
+    if let Some(flag_set_id) = payload.flag_set_id {
+        let metadata = complete_doc
+            .as_object_mut()
+            .ok_or_else(|| {
+                AppError::InternalServerError(format!(
+                    "Flag definition '{}' is not a JSON object",
+                    name
+                ))
+            })?
+            .entry("metadata")
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+
+        match metadata.as_object_mut() {
+            Some(metadata) => {
+                metadata.insert(
+                    "flagSetId".to_string(),
+                    serde_json::Value::String(flag_set_id),
+                );
+            }
+            None => {
+                return Err(AppError::ValidationFailed(format!(
+                    "Cannot set flagSetId, metadata of '{}' is not an object",
+                    name
+                )))
+            }
+        }
+    }
+
+    // The flags of the copy are new, and it must pass the same checks as any other write
+    clear_lifecycle(&mut complete_doc);
+    stamp_lifecycle(None, &mut complete_doc);
+    validate_flags(&state.schemas.load(), &complete_doc)
+        .inspect_err(|_| METRICS.record_validation_failure(&payload.new_name, "schema"))?;
+    let lint = lint_flags(&complete_doc, false)
+        .inspect_err(|_| METRICS.record_validation_failure(&payload.new_name, "semantic"))?;
+
+    // Write next to the target and link it into place, so a flag set created with
+    // the same name in the meantime is not overwritten
+    let temp_path = PathBuf::from(format!(
+        "{}.{}.tmp",
+        new_file_path.display(),
+        uuid::Uuid::new_v4()
+    ));
+    write_flag_document(&temp_path, &complete_doc)?;
+    let linked = link_new_flag_file(&temp_path, &new_file_path, &payload.new_name);
+    let _ = fs::remove_file(&temp_path);
+    linked?;
+    state.search.update(&payload.new_name, &complete_doc);
+
+    Ok((
+        StatusCode::CREATED,
+        etag_header(&complete_doc),
+        Json(FlagDefinitionResponse {
+            name: payload.new_name,
+            content: complete_doc,
+            lint,
+        }),
+    ))
+}
@@ -0,0 +1,455 @@
+use axum::{
+    body::Bytes,
+    extract::{rejection::BytesRejection, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeSet, fs, path::PathBuf};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    archive::{self, FLAG_FILE_SUFFIX},
+    change_requests::is_protected,
+    error::{AppError, AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::{
+        flags::{get_flag_file_path, read_flag_document, write_flag_document, AppState},
+        validate::{validate_document, DocumentReport, NamedDocument, SchemaViolation},
+    },
+    lifecycle::stamp_lifecycle,
+    metrics::METRICS,
+};
+
+/// What to do with flag sets of the upload that already exist
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictMode {
+    /// Reject the whole import
+    #[default]
+    Fail,
+    /// Keep the existing flag set and import the others
+    Skip,
+    /// Replace the existing flag set, unless it is protected
+    Overwrite,
+}
+
+/// Query parameters of the import endpoint
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ImportParams {
+    /// What to do with flag sets that already exist, `fail` by default
+    #[serde(default)]
+    #[param(inline)]
+    pub conflict: ConflictMode,
+    /// Validate the upload and report what would be imported without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Outcome of the import for one flag set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    /// A new flag set was (or, in a dry run, would be) created
+    Created,
+    /// An existing flag set was (or would be) replaced
+    Overwritten,
+    /// The flag set exists and was left unchanged
+    Skipped,
+    /// The flag set exists and the conflict mode is `fail`
+    Conflict,
+    /// The flag set exists and is protected, so it cannot be overwritten
+    Protected,
+    /// The document failed schema or semantic validation
+    Invalid,
+}
+
+/// Import result for one flag set of the upload
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportFileReport {
+    /// Name of the flag set
+    #[schema(example = "production")]
+    pub name: String,
+    pub status: ImportStatus,
+    /// Validation result of the document, absent for skipped flag sets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation: Option<DocumentReport>,
+}
+
+/// Response of an import
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResponse {
+    /// Whether the import was accepted. Nothing is written when it is not, or in a dry run.
+    pub accepted: bool,
+    pub dry_run: bool,
+    /// Writing the flag sets failed and the ones already written were restored, so
+    /// nothing was imported
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub rolled_back: bool,
+    /// Per flag set results, ordered by name
+    pub files: Vec<ImportFileReport>,
+}
+
+/// A flag set of the upload with everything needed to write it
+struct PendingImport {
+    file_path: PathBuf,
+    content: Option<Value>,
+    report: ImportFileReport,
+}
+
+/// Import flag sets from an archive or a JSON array of named documents
+///
+/// Accepts a `tar.gz` or zip archive as produced by `GET /api/export`, or a JSON
+/// array of `{name, content}` documents. Every document is validated before any is
+/// written, and nothing is written unless all of them are accepted.
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    params(ImportParams),
+    request_body(
+        description = "Archive of `*.flagd.json` files with an optional `manifest.json`, or named documents",
+        content(
+            (Vec<u8> = "application/gzip"),
+            (Vec<u8> = "application/zip"),
+            (Vec<NamedDocument> = "application/json")
+        )
+    ),
+    responses(
+        (status = 201, description = "All flag sets were imported or skipped", body = ImportResponse),
+        (status = 200, description = "Dry run completed, nothing was written", body = ImportResponse),
+        (status = 403, description = "An existing flag set is protected, nothing was written", body = ImportResponse),
+        (status = 409, description = "A flag set already exists and the conflict mode is `fail`, nothing was written", body = ImportResponse),
+        (status = 422, description = "At least one document is invalid, nothing was written", body = ImportResponse),
+        (status = 400, description = "Malformed upload (`bad_request`) or invalid flag set name (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 413, description = "Request body too large (`payload_too_large`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 415, description = "Request body is neither an archive nor JSON (`unsupported_media_type`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Writing failed and the import was rolled back (`rolledBack`), or internal server error",
+            content(
+                (ImportResponse = "application/json"),
+                (ProblemDetails = "application/problem+json")
+            )
+        )
+    ),
+    tag = "flags"
+)]
+pub async fn import_flags(
+    State(state): State<AppState>,
+    AppQuery(params): AppQuery<ImportParams>,
+    headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> AppResult<impl IntoResponse> {
+    let body = body?;
+    let documents = tokio::task::spawn_blocking(move || parse_upload(&headers, &body))
+        .await
+        .map_err(|e| AppError::InternalServerError(e.to_string()))??;
+
+    let config = state.config.load();
+    let schemas = state.schemas.load();
+
+    let mut names = BTreeSet::new();
+    let mut pending = Vec::new();
+    for (name, content) in documents {
+        let name = name
+            .strip_suffix(FLAG_FILE_SUFFIX)
+            .unwrap_or(&name)
+            .to_string();
+        let file_path = get_flag_file_path(&config.flags_dir, &name)?;
+        if !names.insert(name.clone()) {
+            return Err(AppError::BadRequest(format!(
+                "Flag set '{}' is part of the upload more than once",
+                name
+            )));
+        }
+
+        let existing = if file_path.exists() {
+            Some(read_flag_document(&file_path)?)
+        } else {
+            None
+        };
+        if existing.is_some() && params.conflict == ConflictMode::Skip {
+            pending.push(PendingImport {
+                report: ImportFileReport {
+                    name,
+                    status: ImportStatus::Skipped,
+                    validation: None,
+                },
+                file_path,
+                content: None,
+            });
+            continue;
+        }
+
+        let (content, validation) = match content {
+            Ok(mut content) => {
+                stamp_lifecycle(existing.as_ref(), &mut content);
+                let validation = validate_document(&schemas, None, &content);
+                (Some(content), validation)
+            }
+            Err(error) => (None, unparsable(error)),
+        };
+
+        let status = match &existing {
+            _ if !validation.valid => ImportStatus::Invalid,
+            None => ImportStatus::Created,
+            Some(_) if params.conflict == ConflictMode::Fail => ImportStatus::Conflict,
+            Some(existing) if is_protected(&config, &name, existing) => ImportStatus::Protected,
+            Some(_) => ImportStatus::Overwritten,
+        };
+        if status == ImportStatus::Invalid {
+            let kind = if validation.schema_errors.is_empty() {
+                "semantic"
+            } else {
+                "schema"
+            };
+            METRICS.record_validation_failure(&name, kind);
+        }
+
+        pending.push(PendingImport {
+            report: ImportFileReport {
+                name,
+                status,
+                validation: Some(validation),
+            },
+            file_path,
+            content,
+        });
+    }
+    pending.sort_by(|a, b| a.report.name.cmp(&b.report.name));
+
+    let has_status = |status| pending.iter().any(|file| file.report.status == status);
+    let rejected = if has_status(ImportStatus::Invalid) {
+        Some(StatusCode::UNPROCESSABLE_ENTITY)
+    } else if has_status(ImportStatus::Protected) {
+        Some(StatusCode::FORBIDDEN)
+    } else if has_status(ImportStatus::Conflict) {
+        Some(StatusCode::CONFLICT)
+    } else {
+        None
+    };
+
+    let mut rolled_back = false;
+    let status = match rejected {
+        Some(status) => status,
+        None if params.dry_run => StatusCode::OK,
+        None => match write_all(&config.flags_dir, &pending) {
+            Ok(()) => {
+                for file in &pending {
+                    if let Some(content) = &file.content {
+                        state.search.update(&file.report.name, content);
+                    }
+                }
+                StatusCode::CREATED
+            }
+            Err(WriteFailure::RolledBack(error)) => {
+                tracing::error!(error = %error, "Import failed and was rolled back");
+                rolled_back = true;
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            Err(WriteFailure::Incomplete(error)) => return Err(error),
+        },
+    };
+
+    Ok((
+        status,
+        Json(ImportResponse {
+            accepted: rejected.is_none() && !rolled_back,
+            dry_run: params.dry_run,
+            rolled_back,
+            files: pending.into_iter().map(|file| file.report).collect(),
+        }),
+    ))
+}
+
+/// Names and parsed contents of the documents of an upload
+fn parse_upload(
+    headers: &HeaderMap,
+    body: &[u8],
+) -> AppResult<Vec<(String, Result<Value, String>)>> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let files = match content_type.as_str() {
+        "application/json" => {
+            let documents = serde_json::from_slice::<Vec<NamedDocument>>(body).map_err(|e| {
+                AppError::BadRequest(format!("Failed to parse named documents: {}", e))
+            })?;
+            return Ok(documents
+                .into_iter()
+                .map(|document| (document.name, Ok(document.content)))
+                .collect());
+        }
+        "application/gzip" | "application/x-gzip" | "application/x-tar+gzip" => {
+            archive::read_tar_gz(body)
+        }
+        "application/zip" | "application/x-zip-compressed" => archive::read_zip(body),
+        _ => {
+            return Err(AppError::UnsupportedMediaType(format!(
+                "Expected application/gzip, application/zip or application/json, got '{}'",
+                content_type
+            )))
+        }
+    }
+    .map_err(|e| AppError::BadRequest(format!("Failed to read archive: {}", e)))?;
+
+    Ok(files
+        .into_iter()
+        .map(|(name, content)| {
+            let content = serde_json::from_slice(&content).map_err(|e| e.to_string());
+            (name, content)
+        })
+        .collect())
+}
+
+/// Validation report of a file that is not JSON
+fn unparsable(error: String) -> DocumentReport {
+    DocumentReport {
+        name: None,
+        valid: false,
+        schema_version: None,
+        schema_errors: vec![SchemaViolation {
+            pointer: String::new(),
+            schema_path: String::new(),
+            message: format!("Failed to parse JSON: {}", error),
+        }],
+        lint: Default::default(),
+    }
+}
+
+/// Write the imported flag sets, first to temporary files that are then renamed, so a
+/// failing write leaves the existing flag sets untouched.
+///
+/// The files being replaced are kept as backups until all renames succeeded. If one
+/// fails, the flag sets already written are restored from their backups or removed.
+fn write_all(flags_dir: &str, pending: &[PendingImport]) -> Result<(), WriteFailure> {
+    fs::create_dir_all(flags_dir).map_err(|e| {
+        WriteFailure::RolledBack(AppError::InternalServerError(format!(
+            "Failed to create flags directory: {}",
+            e
+        )))
+    })?;
+
+    let mut writes = pending
+        .iter()
+        .filter_map(|file| Some((file.content.as_ref()?, &file.file_path)))
+        .map(|(content, file_path)| ImportWrite {
+            file_path,
+            content,
+            temp_path: file_path.with_extension("json.import"),
+            backup_path: file_path.with_extension("json.import-backup"),
+            backed_up: false,
+            replaced: false,
+        })
+        .collect::<Vec<_>>();
+
+    let remove_temp_files = |writes: &[ImportWrite]| {
+        for write in writes {
+            let _ = fs::remove_file(&write.temp_path);
+        }
+    };
+
+    for (index, write) in writes.iter().enumerate() {
+        if let Err(error) = write_flag_document(&write.temp_path, write.content) {
+            remove_temp_files(&writes[..=index]);
+            return Err(WriteFailure::RolledBack(error));
+        }
+    }
+
+    for index in 0..writes.len() {
+        if let Err(error) = writes[index].replace() {
+            METRICS.record_storage_error("write");
+            let failed = writes[index].file_path.display().to_string();
+            remove_temp_files(&writes[index..]);
+            let unrestored = roll_back(&writes[..=index]);
+
+            let message = format!("Failed to write file '{}': {}", failed, error);
+            if unrestored.is_empty() {
+                return Err(WriteFailure::RolledBack(AppError::InternalServerError(
+                    message,
+                )));
+            }
+            return Err(WriteFailure::Incomplete(AppError::InternalServerError(
+                format!(
+                    "{}; rolling back failed for {}",
+                    message,
+                    unrestored.join(", ")
+                ),
+            )));
+        }
+    }
+
+    for write in &writes {
+        if write.backed_up {
+            let _ = fs::remove_file(&write.backup_path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Why writing the flag sets of an import failed
+enum WriteFailure {
+    /// Nothing was written, or everything written was restored
+    RolledBack(AppError),
+    /// Some of the flag sets written could not be restored
+    Incomplete(AppError),
+}
+
+/// A flag set file written by an import
+struct ImportWrite<'a> {
+    file_path: &'a PathBuf,
+    content: &'a Value,
+    /// The new content, written before any flag set is replaced
+    temp_path: PathBuf,
+    /// Link to the replaced file, kept until the import succeeded
+    backup_path: PathBuf,
+    backed_up: bool,
+    /// Whether the new content was moved into place
+    replaced: bool,
+}
+
+impl ImportWrite<'_> {
+    /// Back up the existing file, if any, and move the new content into place
+    fn replace(&mut self) -> std::io::Result<()> {
+        if self.file_path.exists() {
+            // A backup left behind by an import that crashed is outdated
+            let _ = fs::remove_file(&self.backup_path);
+            fs::hard_link(self.file_path, &self.backup_path)?;
+            self.backed_up = true;
+        }
+        fs::rename(&self.temp_path, self.file_path)?;
+        self.replaced = true;
+        Ok(())
+    }
+}
+
+/// Undo the writes of a failed import, returning the files that could not be restored
+fn roll_back(writes: &[ImportWrite]) -> Vec<String> {
+    let mut unrestored = Vec::new();
+    for write in writes.iter().rev() {
+        let restored = match (write.replaced, write.backed_up) {
+            (true, true) => fs::rename(&write.backup_path, write.file_path),
+            // The flag set did not exist before the import
+            (true, false) => fs::remove_file(write.file_path),
+            (false, true) => fs::remove_file(&write.backup_path),
+            (false, false) => Ok(()),
+        };
+
+        if let Err(error) = restored {
+            tracing::error!(
+                path = %write.file_path.display(),
+                error = %error,
+                "Failed to roll back imported flag set"
+            );
+            unrestored.push(format!("'{}'", write.file_path.display()));
+        }
+    }
+    unrestored
+}
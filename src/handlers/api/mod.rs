@@ -1,6 +1,41 @@
+pub mod change_requests;
+pub mod convert;
+pub mod diff;
+pub mod export;
 pub mod flags;
+pub mod import;
+pub mod promote;
+pub mod reports;
+pub mod rollouts;
+pub mod schedules;
+pub mod search;
+pub mod validate;
 
+pub use change_requests::{
+    approve_change_request, get_change_request, list_change_requests, reject_change_request,
+    ListChangeRequestsResponse, ReviewRequest,
+};
+pub use convert::{convert_flags, ConvertResponse};
+pub use diff::diff_flag_sets;
+pub use export::export_flags;
 pub use flags::{
-    create_flag, delete_flag, get_flag, init_app_state, list_flags, update_flag, AppState,
-    CreateFlagRequest, FlagDefinitionResponse, ListFlagsResponse, UpdateFlagRequest,
+    copy_flag, create_flag, delete_flag, get_flag, init_app_state, list_flags, rename_flag,
+    update_flag, AppState, CopyFlagRequest, CreateFlagRequest, FlagDefinitionResponse,
+    FlagSetSummary, ListFlagsResponse, RenameFlagRequest, UpdateFlagRequest, ValidationStatus,
+};
+pub use import::{import_flags, ImportFileReport, ImportResponse, ImportStatus};
+pub use promote::{promote_flags, PromoteRequest, PromoteResponse};
+pub use reports::{stale_report, StaleReport};
+pub use rollouts::{
+    create_rollout, list_rollouts, pause_rollout, resume_rollout, rollback_rollout,
+    CreateRolloutRequest, ListRolloutsResponse,
+};
+pub use schedules::{
+    cancel_schedule, create_schedule, list_schedules, run_scheduler, CreateScheduleRequest,
+    ListSchedulesResponse,
+};
+pub use search::{search_flags, SearchResponse};
+pub use validate::{
+    validate_documents, DocumentReport, NamedDocument, SchemaViolation, ValidateRequest,
+    ValidateResponse,
 };
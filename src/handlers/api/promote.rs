@@ -0,0 +1,325 @@
+use axum::{extract::State, http::HeaderMap, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use utoipa::ToSchema;
+
+use crate::{
+    change_requests::{is_protected, ChangeRequest},
+    diff::{diff_documents, FlagSetDiff},
+    document::flag_entries,
+    error::{AppError, AppJson, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::change_requests::propose_change,
+    handlers::api::flags::{
+        check_if_match, etag_header, get_flag_file_path, lint_flags, read_flag_document,
+        validate_flags, write_flag_document, AppState,
+    },
+    lifecycle::stamp_lifecycle,
+    lint::LintReport,
+    metrics::METRICS,
+};
+
+/// Top-level field of the shared evaluators of a flag set
+const EVALUATORS: &str = "$evaluators";
+
+/// Request payload for promoting flags from one flag set to another
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoteRequest {
+    /// Flag set to promote from
+    #[schema(example = "staging")]
+    pub source: String,
+    /// Flag set to promote to
+    #[schema(example = "prod")]
+    pub target: String,
+    /// Keys of the flags to promote, all flags of the source if omitted
+    #[schema(example = json!(["new-checkout"]))]
+    pub flags: Option<Vec<String>>,
+    /// Flag fields that keep their value in the target, e.g. `targeting` or `defaultVariant`.
+    /// With `targeting` or `$evaluators` pinned, shared evaluators that already exist in
+    /// the target keep their value too.
+    #[serde(default)]
+    #[schema(example = json!(["targeting", "defaultVariant"]))]
+    pub pinned: Vec<String>,
+    /// Compute and validate the result without writing it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Result of a promotion
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoteResponse {
+    /// Flag set promoted from
+    #[schema(example = "staging")]
+    pub source: String,
+    /// Flag set promoted to
+    #[schema(example = "prod")]
+    pub target: String,
+    /// Whether the target was written
+    pub applied: bool,
+    /// Shared evaluators added to or updated in the target because promoted flags
+    /// refer to them
+    #[schema(example = json!(["is-beta-user"]))]
+    pub evaluators: Vec<String>,
+    /// Changes to the target flag set
+    pub changes: FlagSetDiff,
+    /// Complete target flag set after the promotion
+    #[schema(value_type = Object)]
+    pub content: Value,
+    /// Result of the semantic lint pass on the target
+    pub lint: LintReport,
+}
+
+/// Promote flags from one flag set to another
+#[utoipa::path(
+    post,
+    path = "/api/promote",
+    params(
+        ("If-Match" = Option<String>, Header, description = "Only write if the target flag set still has this `ETag`")
+    ),
+    request_body = PromoteRequest,
+    responses(
+        (status = 200, description = "Flags promoted, or the changes a dry run would make", body = PromoteResponse),
+        (status = 202, description = "Target is protected, a pending change request was created", body = ChangeRequest),
+        (status = 401, description = "Target is protected and no authenticated user is known (`unauthorized`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 400, description = "Malformed request (`bad_request`), invalid name (`invalid_name`) or the result fails validation (`validation_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Source or target flag set not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 412, description = "`If-Match` does not match the current `ETag` of the target (`precondition_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "flags"
+)]
+pub async fn promote_flags(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AppJson(payload): AppJson<PromoteRequest>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", payload.target.as_str());
+    let flags_dir = state.config.load().flags_dir.clone();
+
+    let source_path = get_flag_file_path(&flags_dir, &payload.source)?;
+    let target_path = get_flag_file_path(&flags_dir, &payload.target)?;
+    if payload.source == payload.target {
+        return Err(AppError::BadRequest(
+            "Source and target must be different flag sets".to_string(),
+        ));
+    }
+    for (name, path) in [
+        (&payload.source, &source_path),
+        (&payload.target, &target_path),
+    ] {
+        if !path.exists() {
+            return Err(AppError::NotFound(format!(
+                "Flag definition '{}' not found",
+                name
+            )));
+        }
+    }
+
+    let source = read_flag_document(&source_path)?;
+    let target = read_flag_document(&target_path)?;
+    check_if_match(&headers, &payload.target, &target)?;
+
+    let (mut promoted, evaluators) =
+        promote(&source, &target, payload.flags.as_deref(), &payload.pinned).map_err(|key| {
+            AppError::BadRequest(format!(
+                "Flag '{}' does not exist in '{}'",
+                key, payload.source
+            ))
+        })?;
+
+    stamp_lifecycle(Some(&target), &mut promoted);
+
+    validate_flags(&state.schemas.load(), &promoted)
+        .inspect_err(|_| METRICS.record_validation_failure(&payload.target, "schema"))?;
+    let lint = lint_flags(&promoted, payload.dry_run)
+        .inspect_err(|_| METRICS.record_validation_failure(&payload.target, "semantic"))?;
+
+    let changes = diff_documents(&target, &promoted);
+
+    // Promotions into protected flag sets wait for the approval of another user
+    if !payload.dry_run
+        && !changes.is_empty()
+        && is_protected(&state.config.load(), &payload.target, &target)
+    {
+        return propose_change(&state, &headers, &payload.target, &target, promoted);
+    }
+
+    let applied = !payload.dry_run && !changes.is_empty();
+    if applied {
+        write_flag_document(&target_path, &promoted)?;
+        state.search.update(&payload.target, &promoted);
+        tracing::info!(
+            source = %payload.source,
+            flags = changes.added.len() + changes.modified.len(),
+            "Promoted flags"
+        );
+    }
+
+    Ok((
+        etag_header(if applied { &promoted } else { &target }),
+        Json(PromoteResponse {
+            source: payload.source,
+            target: payload.target,
+            applied,
+            evaluators,
+            changes,
+            content: promoted,
+            lint,
+        }),
+    )
+        .into_response())
+}
+
+/// Copy the selected flags of `source` into `target`, keeping the target's value of
+/// pinned fields for flags that already exist there, together with the shared
+/// evaluators their targeting refers to. Returns the result and the names of the
+/// evaluators it changed.
+///
+/// Fails with the key of a selected flag that `source` does not contain.
+fn promote(
+    source: &Value,
+    target: &Value,
+    selected: Option<&[String]>,
+    pinned: &[String],
+) -> Result<(Value, Vec<String>), String> {
+    let source_flags = flag_entries(source)
+        .into_iter()
+        .map(|(key, flag, _)| (key, flag.clone()))
+        .collect::<Vec<_>>();
+
+    let keys = match selected {
+        Some(keys) => keys.to_vec(),
+        None => source_flags.iter().map(|(key, _)| key.clone()).collect(),
+    };
+
+    let mut result = target.clone();
+    let mut referenced = Vec::new();
+    for key in keys {
+        let Some((_, flag)) = source_flags.iter().find(|(k, _)| *k == key) else {
+            return Err(key);
+        };
+        let mut flag = flag.clone();
+
+        if let (Some(existing), Some(fields)) = (find_flag(&result, &key), flag.as_object_mut()) {
+            for field in pinned {
+                match existing.get(field) {
+                    Some(value) => fields.insert(field.clone(), value.clone()),
+                    None => fields.remove(field),
+                };
+            }
+        }
+
+        collect_refs(&flag, &mut referenced);
+        put_flag(&mut result, &key, flag);
+    }
+
+    let evaluators = promote_evaluators(source, &mut result, referenced, pinned);
+    Ok((result, evaluators))
+}
+
+/// Copy the evaluators named in `referenced` from `source` into `result`, following
+/// the `$ref`s of the copied evaluators. Evaluators the source does not define are
+/// left to the linter to report.
+fn promote_evaluators(
+    source: &Value,
+    result: &mut Value,
+    mut referenced: Vec<String>,
+    pinned: &[String],
+) -> Vec<String> {
+    let source_evaluators = source.get(EVALUATORS).and_then(Value::as_object);
+    let keep_existing = pinned
+        .iter()
+        .any(|field| field == "targeting" || field == EVALUATORS);
+
+    let mut visited = BTreeSet::new();
+    let mut changed = Vec::new();
+    while let Some(name) = referenced.pop() {
+        if !visited.insert(name.clone()) {
+            continue;
+        }
+        let Some(evaluator) = source_evaluators.and_then(|map| map.get(&name)) else {
+            continue;
+        };
+        let existing = result
+            .get(EVALUATORS)
+            .and_then(Value::as_object)
+            .and_then(|map| map.get(&name));
+        if existing.is_some() && keep_existing {
+            continue;
+        }
+
+        collect_refs(evaluator, &mut referenced);
+        if existing != Some(evaluator) {
+            let Some(doc) = result.as_object_mut() else {
+                break;
+            };
+            let evaluators = doc
+                .entry(EVALUATORS)
+                .or_insert_with(|| Value::Object(Default::default()));
+            if let Some(evaluators) = evaluators.as_object_mut() {
+                evaluators.insert(name.clone(), evaluator.clone());
+                changed.push(name);
+            }
+        }
+    }
+
+    changed.sort();
+    changed
+}
+
+/// Names of the evaluators a JSON Logic rule refers to with `$ref`
+fn collect_refs(rule: &Value, names: &mut Vec<String>) {
+    match rule {
+        Value::Object(ops) => {
+            for (op, args) in ops {
+                match (op.as_str(), args) {
+                    ("$ref", Value::String(name)) => names.push(name.clone()),
+                    _ => collect_refs(args, names),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_refs(item, names)),
+        _ => {}
+    }
+}
+
+fn find_flag<'a>(doc: &'a Value, key: &str) -> Option<&'a Value> {
+    flag_entries(doc)
+        .into_iter()
+        .find(|(k, _, _)| k == key)
+        .map(|(_, flag, _)| flag)
+}
+
+/// Insert or replace a flag, in the object or array form the document uses
+fn put_flag(doc: &mut Value, key: &str, mut flag: Value) {
+    match doc.get_mut("flags") {
+        Some(Value::Array(flags)) => {
+            if let Some(fields) = flag.as_object_mut() {
+                fields.insert("key".to_string(), Value::String(key.to_string()));
+            }
+            match flags
+                .iter_mut()
+                .find(|existing| existing.get("key").and_then(Value::as_str) == Some(key))
+            {
+                Some(existing) => *existing = flag,
+                None => flags.push(flag),
+            }
+        }
+        Some(Value::Object(flags)) => {
+            if let Some(fields) = flag.as_object_mut() {
+                fields.remove("key");
+            }
+            flags.insert(key.to_string(), flag);
+        }
+        _ => {
+            if let Some(fields) = flag.as_object_mut() {
+                fields.remove("key");
+            }
+            if let Some(doc) = doc.as_object_mut() {
+                doc.insert("flags".to_string(), serde_json::json!({ key: flag }));
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    error::{AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::flags::{get_flag_file_path, list_flag_set_names, read_flag_document, AppState},
+    lifecycle::{self, StaleFlag},
+};
+
+/// Days a flag must have been `DISABLED` to be reported, unless requested otherwise
+const DEFAULT_DISABLED_DAYS: u32 = 30;
+
+/// Query parameters of the stale-flag report
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct StaleReportParams {
+    /// Report flags that have been `DISABLED` for at least this many days (default 30)
+    #[param(example = 30)]
+    pub disabled_days: Option<u32>,
+}
+
+/// Flags that are candidates for removal, across all flag sets
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleReport {
+    /// Date the report was generated for, in UTC
+    #[schema(example = "2026-03-01")]
+    pub date: String,
+    /// Days a flag must have been `DISABLED` to be reported
+    #[schema(example = 30)]
+    pub disabled_days: u32,
+    /// Stale flags, ordered by flag set and flag
+    pub flags: Vec<StaleFlag>,
+    /// Flag sets that could not be read or parsed and were left out of the report
+    #[schema(example = json!(["broken"]))]
+    pub unreadable_flag_sets: Vec<String>,
+}
+
+/// Report expired flags, flags disabled for a number of days and flags with a single variant
+#[utoipa::path(
+    get,
+    path = "/api/reports/stale",
+    params(StaleReportParams),
+    responses(
+        (status = 200, description = "Stale flags across all flag sets", body = StaleReport),
+        (status = 400, description = "Invalid query parameters (`bad_request`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "reports"
+)]
+pub async fn stale_report(
+    State(state): State<AppState>,
+    AppQuery(params): AppQuery<StaleReportParams>,
+) -> AppResult<impl IntoResponse> {
+    let flags_dir = state.config.load().flags_dir.clone();
+    let disabled_days = params.disabled_days.unwrap_or(DEFAULT_DISABLED_DAYS);
+    let today = lifecycle::today();
+
+    let mut flags = Vec::new();
+    let mut unreadable_flag_sets = Vec::new();
+    for name in list_flag_set_names(&flags_dir)? {
+        // One corrupt flag set should not hide the stale flags of all others
+        let doc = match read_flag_document(&get_flag_file_path(&flags_dir, &name)?) {
+            Ok(doc) => doc,
+            Err(e) => {
+                tracing::warn!(flag_set = %name, error = %e, "Skipping unreadable flag set in stale report");
+                unreadable_flag_sets.push(name);
+                continue;
+            }
+        };
+        let mut stale = lifecycle::stale_flags(&name, &doc, today, disabled_days);
+        stale.sort_by(|a, b| a.flag.cmp(&b.flag));
+        flags.extend(stale);
+    }
+
+    Ok(Json(StaleReport {
+        date: lifecycle::format_date(today),
+        disabled_days,
+        flags,
+        unreadable_flag_sets,
+    }))
+}
@@ -0,0 +1,451 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    actor::actor,
+    document::flag_entries,
+    error::{AppError, AppJson, AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::{
+        flags::{get_flag_file_path, read_flag_document, AppState},
+        schedules::apply_change,
+    },
+    records::now,
+    rollouts::{Rollout, RolloutStatus, RolloutStore},
+};
+
+/// Request payload for starting a progressive rollout
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRolloutRequest {
+    /// Key of the flag
+    #[schema(example = "new-checkout")]
+    pub flag: String,
+    /// Variant to roll out
+    #[schema(example = "on")]
+    pub variant: String,
+    /// Variant served outside the rollout percentage, defaults to the flag's
+    /// `defaultVariant`
+    #[schema(example = "off")]
+    pub baseline_variant: Option<String>,
+    /// Bucketing expression of the `fractional` split
+    #[schema(value_type = Option<Object>, example = json!({"var": "email"}))]
+    pub bucket_by: Option<Value>,
+    /// Strictly increasing percentages between 1 and 100, the first is applied immediately
+    #[schema(example = json!([1, 5, 25, 50, 100]))]
+    pub steps: Vec<u8>,
+    /// Seconds between two steps
+    #[schema(example = 7200)]
+    pub interval_seconds: u64,
+}
+
+/// Query parameters for rolling back a rollout
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RollbackParams {
+    /// Restore the original targeting even if it was changed outside the rollout
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Response for listing the rollouts of a flag set
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListRolloutsResponse {
+    /// Rollouts, oldest first
+    pub rollouts: Vec<Rollout>,
+}
+
+/// Start a progressive rollout of a variant
+#[utoipa::path(
+    post,
+    path = "/api/flags/{name}/rollouts",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file")
+    ),
+    request_body = CreateRolloutRequest,
+    responses(
+        (status = 201, description = "Rollout started and its first step applied", body = Rollout),
+        (status = 400, description = "Invalid plan or unknown flag (`bad_request`), first step fails validation (`validation_failed`), or invalid name (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 403, description = "Flag set is protected (`forbidden`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Flag definition not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "The flag already has a running rollout (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "rollouts"
+)]
+pub async fn create_rollout(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    AppJson(payload): AppJson<CreateRolloutRequest>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    validate_plan(&payload)?;
+
+    let flags_dir = state.config.load().flags_dir.clone();
+    let file_path = get_flag_file_path(&flags_dir, &name)?;
+    if !file_path.exists() {
+        return Err(AppError::NotFound(format!(
+            "Flag definition '{}' not found",
+            name
+        )));
+    }
+
+    let store = RolloutStore::new(&flags_dir);
+    let _lock = store.lock();
+
+    if store.list()?.iter().any(|rollout| {
+        rollout.flag_set == name && rollout.flag == payload.flag && rollout.is_running()
+    }) {
+        return Err(AppError::Conflict(format!(
+            "Flag '{}' already has a running rollout",
+            payload.flag
+        )));
+    }
+
+    let current = read_flag_document(&file_path)?;
+    let Some((_, flag, _)) = flag_entries(&current)
+        .into_iter()
+        .find(|(key, _, _)| *key == payload.flag)
+    else {
+        return Err(AppError::BadRequest(format!(
+            "Flag '{}' does not exist",
+            payload.flag
+        )));
+    };
+
+    let baseline_variant = match payload.baseline_variant {
+        Some(variant) => variant,
+        None => flag
+            .get("defaultVariant")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "Flag '{}' has no defaultVariant, baselineVariant is required",
+                    payload.flag
+                ))
+            })?,
+    };
+    if baseline_variant == payload.variant {
+        return Err(AppError::BadRequest(
+            "variant and baselineVariant must differ".to_string(),
+        ));
+    }
+
+    let mut rollout = Rollout::new(
+        &name,
+        payload.flag,
+        payload.variant,
+        baseline_variant,
+        payload.bucket_by,
+        payload.steps,
+        payload.interval_seconds,
+        actor(&headers),
+    );
+    rollout.original_targeting = flag.get("targeting").cloned();
+
+    apply_change(&state, &name, None, |doc| rollout.apply_step(doc))?;
+    rollout.step_applied();
+    store.save(&rollout)?;
+
+    tracing::info!(
+        rollout = %rollout.id,
+        flag = %rollout.flag,
+        percentage = rollout.percentage(),
+        "Started rollout"
+    );
+
+    Ok((StatusCode::CREATED, Json(rollout)))
+}
+
+/// List the rollouts of a flag set
+#[utoipa::path(
+    get,
+    path = "/api/flags/{name}/rollouts",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file")
+    ),
+    responses(
+        (status = 200, description = "Rollouts of the flag set, oldest first", body = ListRolloutsResponse),
+        (status = 400, description = "Invalid name (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "rollouts"
+)]
+pub async fn list_rollouts(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let flags_dir = state.config.load().flags_dir.clone();
+    get_flag_file_path(&flags_dir, &name)?;
+
+    let rollouts = RolloutStore::new(&flags_dir)
+        .list()?
+        .into_iter()
+        .filter(|rollout| rollout.flag_set == name)
+        .collect();
+
+    Ok(Json(ListRolloutsResponse { rollouts }))
+}
+
+/// Pause an active rollout at its current step
+#[utoipa::path(
+    post,
+    path = "/api/flags/{name}/rollouts/{id}/pause",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file"),
+        ("id" = String, Path, description = "Identifier of the rollout")
+    ),
+    responses(
+        (status = 200, description = "Rollout paused", body = Rollout),
+        (status = 404, description = "Rollout not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Rollout is not active (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "rollouts"
+)]
+pub async fn pause_rollout(
+    State(state): State<AppState>,
+    Path((name, id)): Path<(String, String)>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let store = RolloutStore::new(&state.config.load().flags_dir);
+    let _lock = store.lock();
+
+    let mut rollout = get_rollout(&store, &name, &id)?;
+    ensure_status(&rollout, &[RolloutStatus::Active], "paused")?;
+
+    rollout.pause();
+    store.save(&rollout)?;
+
+    tracing::info!(rollout = %rollout.id, "Paused rollout");
+
+    Ok(Json(rollout))
+}
+
+/// Resume a paused rollout, or retry the step a failed or conflicting rollout stopped at
+///
+/// Resuming after a conflict overwrites the changed targeting with the current step.
+#[utoipa::path(
+    post,
+    path = "/api/flags/{name}/rollouts/{id}/resume",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file"),
+        ("id" = String, Path, description = "Identifier of the rollout")
+    ),
+    responses(
+        (status = 200, description = "Rollout resumed", body = Rollout),
+        (status = 404, description = "Rollout not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Rollout is not paused, failed or stopped by a conflict (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "rollouts"
+)]
+pub async fn resume_rollout(
+    State(state): State<AppState>,
+    Path((name, id)): Path<(String, String)>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let store = RolloutStore::new(&state.config.load().flags_dir);
+    let _lock = store.lock();
+
+    let mut rollout = get_rollout(&store, &name, &id)?;
+    ensure_status(
+        &rollout,
+        &[
+            RolloutStatus::Paused,
+            RolloutStatus::Failed,
+            RolloutStatus::Conflict,
+        ],
+        "resumed",
+    )?;
+
+    rollout.resume();
+    store.save(&rollout)?;
+
+    tracing::info!(rollout = %rollout.id, "Resumed rollout");
+
+    Ok(Json(rollout))
+}
+
+/// Roll back a rollout, restoring the flag's targeting from before it started
+///
+/// If the targeting was changed outside the rollout since its last step, the rollback
+/// is refused unless `force` is set, so the change is not lost unnoticed.
+#[utoipa::path(
+    post,
+    path = "/api/flags/{name}/rollouts/{id}/rollback",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file"),
+        ("id" = String, Path, description = "Identifier of the rollout"),
+        RollbackParams
+    ),
+    responses(
+        (status = 200, description = "Rollout rolled back", body = Rollout),
+        (status = 400, description = "Restored flag set fails validation (`validation_failed`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 403, description = "Flag set is protected (`forbidden`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Rollout not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Rollout was already rolled back, or the targeting was changed outside the rollout (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "rollouts"
+)]
+pub async fn rollback_rollout(
+    State(state): State<AppState>,
+    Path((name, id)): Path<(String, String)>,
+    AppQuery(params): AppQuery<RollbackParams>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let store = RolloutStore::new(&state.config.load().flags_dir);
+    let _lock = store.lock();
+
+    let mut rollout = get_rollout(&store, &name, &id)?;
+    ensure_status(
+        &rollout,
+        &[
+            RolloutStatus::Active,
+            RolloutStatus::Paused,
+            RolloutStatus::Failed,
+            RolloutStatus::Conflict,
+            RolloutStatus::Completed,
+        ],
+        "rolled back",
+    )?;
+
+    let mut conflict = false;
+    apply_change(&state, &name, None, |doc| {
+        if !params.force {
+            rollout
+                .check_targeting(doc)
+                .inspect_err(|_| conflict = true)?;
+        }
+        rollout.restore(doc)
+    })
+    .map_err(|e| match e {
+        AppError::BadRequest(message) if conflict => AppError::Conflict(format!(
+            "{}, set force to restore the original targeting anyway",
+            message
+        )),
+        e => e,
+    })?;
+    rollout.rolled_back();
+    store.save(&rollout)?;
+
+    tracing::info!(rollout = %rollout.id, "Rolled back rollout");
+
+    Ok(Json(rollout))
+}
+
+/// Apply the next step of all active rollouts that are due, recording their outcome
+pub(crate) fn run_due_rollouts(state: &AppState) -> AppResult<()> {
+    let store = RolloutStore::new(&state.config.load().flags_dir);
+    let _lock = store.lock();
+
+    let now = now();
+    for mut rollout in store.list()? {
+        if !rollout.is_due(now) {
+            continue;
+        }
+
+        // A failed retry stays at the step that could not be applied
+        if rollout.error.is_none() {
+            rollout.advance();
+        }
+
+        let mut conflict = false;
+        let result = apply_change(state, &rollout.flag_set, None, |doc| {
+            rollout
+                .check_targeting(doc)
+                .inspect_err(|_| conflict = true)?;
+            rollout.apply_step(doc)
+        });
+        match result {
+            Ok(_) => {
+                tracing::info!(
+                    rollout = %rollout.id,
+                    flag_set = %rollout.flag_set,
+                    flag = %rollout.flag,
+                    percentage = rollout.percentage(),
+                    "Advanced rollout"
+                );
+                rollout.step_applied();
+            }
+            Err(e) if conflict => {
+                tracing::warn!(
+                    rollout = %rollout.id,
+                    flag_set = %rollout.flag_set,
+                    flag = %rollout.flag,
+                    error = %e,
+                    "Stopped rollout after a conflicting change"
+                );
+                rollout.conflicted(e.to_string());
+            }
+            Err(e) => {
+                tracing::warn!(
+                    rollout = %rollout.id,
+                    flag_set = %rollout.flag_set,
+                    flag = %rollout.flag,
+                    error = %e,
+                    "Failed to advance rollout"
+                );
+                rollout.step_failed(e.to_string());
+            }
+        }
+        store.save(&rollout)?;
+    }
+
+    Ok(())
+}
+
+fn validate_plan(payload: &CreateRolloutRequest) -> AppResult<()> {
+    if payload.steps.is_empty() {
+        return Err(AppError::BadRequest(
+            "A rollout needs at least one step".to_string(),
+        ));
+    }
+
+    if payload.steps.iter().any(|step| !(1..=100).contains(step))
+        || payload.steps.windows(2).any(|pair| pair[0] >= pair[1])
+    {
+        return Err(AppError::BadRequest(
+            "Steps must be strictly increasing percentages between 1 and 100".to_string(),
+        ));
+    }
+
+    if payload.interval_seconds == 0 {
+        return Err(AppError::BadRequest(
+            "intervalSeconds must be greater than 0".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// A rollout of the flag set
+fn get_rollout(store: &RolloutStore, name: &str, id: &str) -> AppResult<Rollout> {
+    let rollout = store.get(id)?;
+    if rollout.flag_set != name {
+        return Err(AppError::NotFound(format!("Rollout '{}' not found", id)));
+    }
+    Ok(rollout)
+}
+
+fn ensure_status(rollout: &Rollout, allowed: &[RolloutStatus], action: &str) -> AppResult<()> {
+    if !allowed.contains(&rollout.status) {
+        return Err(AppError::Conflict(format!(
+            "Rollout '{}' is {} and cannot be {}",
+            rollout.id,
+            rollout.status.label(),
+            action
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,349 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::{
+    actor::actor,
+    change_requests::{is_protected, ChangeRequestStatus, ChangeRequestStore},
+    error::{AppError, AppJson, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::{
+        change_requests::new_change_request,
+        flags::{
+            document_etag, get_flag_file_path, lint_flags, read_flag_document, validate_flags,
+            write_flag_document, AppState,
+        },
+        rollouts::run_due_rollouts,
+    },
+    lifecycle::stamp_lifecycle,
+    metrics::METRICS,
+    records::now,
+    schedules::{Schedule, ScheduleStatus, ScheduleStore, ScheduledChange},
+};
+
+/// How often the scheduler looks for due schedules
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Request payload for scheduling a change to a flag set
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateScheduleRequest {
+    /// Unix timestamp in seconds when the change is applied, must be in the future
+    #[schema(example = 1767236400)]
+    pub run_at: u64,
+    pub change: ScheduledChange,
+}
+
+/// Response for listing the schedules of a flag set
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListSchedulesResponse {
+    /// Schedules, earliest first
+    pub schedules: Vec<Schedule>,
+}
+
+/// Schedule a change to a flag set
+///
+/// Changes to protected flag sets are `proposed` with a change request and become
+/// `pending` once another user approves it. They only run if the flag set is still
+/// the version the change request was created against.
+#[utoipa::path(
+    post,
+    path = "/api/flags/{name}/schedules",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file")
+    ),
+    request_body = CreateScheduleRequest,
+    responses(
+        (status = 201, description = "Change scheduled", body = Schedule),
+        (status = 202, description = "Flag set is protected, the schedule awaits approval of its change request", body = Schedule),
+        (status = 400, description = "Time not in the future or invalid change (`bad_request`), change fails validation (`validation_failed`), or invalid name (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 401, description = "Flag set is protected and there is no authenticated user (`unauthorized`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 404, description = "Flag definition not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "schedules"
+)]
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    AppJson(payload): AppJson<CreateScheduleRequest>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let config = state.config.load();
+    let file_path = get_flag_file_path(&config.flags_dir, &name)?;
+
+    // Held until the schedule is stored, so the flag set cannot be renamed meanwhile
+    let store = ScheduleStore::new(&config.flags_dir);
+    let _lock = store.lock();
+
+    if !file_path.exists() {
+        return Err(AppError::NotFound(format!(
+            "Flag definition '{}' not found",
+            name
+        )));
+    }
+
+    let current = read_flag_document(&file_path)?;
+
+    if payload.run_at <= now() {
+        return Err(AppError::BadRequest(
+            "runAt must be a Unix timestamp in the future".to_string(),
+        ));
+    }
+
+    // Reject changes that would not apply to the flag set as it is now
+    let proposed = payload
+        .change
+        .apply(&current)
+        .map_err(AppError::BadRequest)?;
+    validate_flags(&state.schemas.load(), &proposed)
+        .inspect_err(|_| METRICS.record_validation_failure(&name, "schema"))?;
+    lint_flags(&proposed, false)
+        .inspect_err(|_| METRICS.record_validation_failure(&name, "semantic"))?;
+
+    let mut schedule = Schedule::new(&name, payload.run_at, payload.change, actor(&headers));
+
+    if is_protected(&config, &name, &current) {
+        let mut request = new_change_request(&headers, &name, &current, proposed)?;
+        request.schedule = Some(schedule.id.clone());
+        schedule.status = ScheduleStatus::Proposed;
+        schedule.change_request = Some(request.id.clone());
+
+        // Stored first, so the change request never refers to a missing schedule
+        store.save(&schedule)?;
+        ChangeRequestStore::new(&config.flags_dir).save(&request)?;
+
+        tracing::info!(
+            schedule = %schedule.id,
+            change_request = %request.id,
+            "Scheduled change awaiting approval"
+        );
+
+        return Ok((StatusCode::ACCEPTED, Json(schedule)));
+    }
+
+    store.save(&schedule)?;
+
+    tracing::info!(schedule = %schedule.id, run_at = schedule.run_at, "Scheduled change");
+
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+/// List the schedules of a flag set
+#[utoipa::path(
+    get,
+    path = "/api/flags/{name}/schedules",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file")
+    ),
+    responses(
+        (status = 200, description = "Schedules of the flag set, earliest first", body = ListSchedulesResponse),
+        (status = 400, description = "Invalid name (`invalid_name`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "schedules"
+)]
+pub async fn list_schedules(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let flags_dir = state.config.load().flags_dir.clone();
+    get_flag_file_path(&flags_dir, &name)?;
+
+    let schedules = ScheduleStore::new(&flags_dir)
+        .list()?
+        .into_iter()
+        .filter(|schedule| schedule.flag_set == name)
+        .collect();
+
+    Ok(Json(ListSchedulesResponse { schedules }))
+}
+
+/// Cancel a pending or proposed schedule
+#[utoipa::path(
+    delete,
+    path = "/api/flags/{name}/schedules/{id}",
+    params(
+        ("name" = String, Path, description = "Name of the flag definition file"),
+        ("id" = String, Path, description = "Identifier of the schedule")
+    ),
+    responses(
+        (status = 200, description = "Schedule cancelled", body = Schedule),
+        (status = 404, description = "Schedule not found", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 409, description = "Schedule already ran or was cancelled (`conflict`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "schedules"
+)]
+pub async fn cancel_schedule(
+    State(state): State<AppState>,
+    Path((name, id)): Path<(String, String)>,
+) -> AppResult<impl IntoResponse> {
+    tracing::Span::current().record("flag_set", name.as_str());
+    let store = ScheduleStore::new(&state.config.load().flags_dir);
+    let _lock = store.lock();
+
+    let mut schedule = store.get(&id)?;
+    if schedule.flag_set != name {
+        return Err(AppError::NotFound(format!("Schedule '{}' not found", id)));
+    }
+
+    if !matches!(
+        schedule.status,
+        ScheduleStatus::Pending | ScheduleStatus::Proposed
+    ) {
+        return Err(AppError::Conflict(format!(
+            "Schedule '{}' is no longer pending",
+            id
+        )));
+    }
+
+    schedule.finish(ScheduleStatus::Cancelled, None);
+    store.save(&schedule)?;
+
+    tracing::info!(schedule = %schedule.id, "Cancelled schedule");
+
+    Ok(Json(schedule))
+}
+
+/// Apply due schedules and rollout steps until the process exits
+pub async fn run_scheduler(state: AppState) -> Result<(), String> {
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || {
+            // Run independently, so a failure of one does not hold up the other. Storage
+            // errors are retried on the next tick.
+            if let Err(e) = run_due_schedules(&state) {
+                tracing::error!(error = %e, "Failed to run due schedules");
+            }
+            if let Err(e) = run_due_rollouts(&state) {
+                tracing::error!(error = %e, "Failed to run due rollouts");
+            }
+        })
+        .await
+        .map_err(|e| format!("scheduler panicked: {}", e))?;
+    }
+}
+
+/// Apply all pending schedules whose time has come, recording their outcome
+fn run_due_schedules(state: &AppState) -> AppResult<()> {
+    let store = ScheduleStore::new(&state.config.load().flags_dir);
+    let _lock = store.lock();
+
+    let now = now();
+    for mut schedule in store.list()? {
+        if !schedule.is_due(now) {
+            continue;
+        }
+
+        let result = approved_base(state, &schedule).and_then(|base| {
+            apply_change(state, &schedule.flag_set, base.as_deref(), |doc| {
+                schedule.change.apply(doc)
+            })
+        });
+        match result {
+            Ok(_) => {
+                tracing::info!(
+                    schedule = %schedule.id,
+                    flag_set = %schedule.flag_set,
+                    "Applied scheduled change"
+                );
+                schedule.finish(ScheduleStatus::Applied, None);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    schedule = %schedule.id,
+                    flag_set = %schedule.flag_set,
+                    error = %e,
+                    "Failed to apply scheduled change"
+                );
+                schedule.finish(ScheduleStatus::Failed, Some(e.to_string()));
+            }
+        }
+        store.save(&schedule)?;
+    }
+
+    Ok(())
+}
+
+/// `ETag` of the flag set the change request of a schedule was approved against
+fn approved_base(state: &AppState, schedule: &Schedule) -> AppResult<Option<String>> {
+    let Some(id) = &schedule.change_request else {
+        return Ok(None);
+    };
+
+    let request = ChangeRequestStore::new(&state.config.load().flags_dir).get(id)?;
+    if request.status != ChangeRequestStatus::Approved {
+        return Err(AppError::Forbidden(format!(
+            "Change request '{}' is not approved",
+            id
+        )));
+    }
+
+    Ok(Some(request.base_etag))
+}
+
+/// Apply a change to a flag set on behalf of the server, through the same checks as
+/// a regular write, returning the written document.
+///
+/// Protected flag sets are only changed with the `ETag` of an `approved_base`, the
+/// version of the flag set an approved change request was created against.
+pub(crate) fn apply_change(
+    state: &AppState,
+    name: &str,
+    approved_base: Option<&str>,
+    change: impl FnOnce(&Value) -> Result<Value, String>,
+) -> AppResult<Value> {
+    let config = state.config.load();
+    let file_path = get_flag_file_path(&config.flags_dir, name)?;
+
+    if !file_path.exists() {
+        return Err(AppError::NotFound(format!(
+            "Flag definition '{}' not found",
+            name
+        )));
+    }
+
+    let current = read_flag_document(&file_path)?;
+    match approved_base {
+        Some(etag) if document_etag(&current) != etag => {
+            return Err(AppError::Conflict(format!(
+                "Flag definition '{}' was modified after the change request was created",
+                name
+            )));
+        }
+        Some(_) => {}
+        // The flag set may have been protected after the change was planned
+        None if is_protected(&config, name, &current) => {
+            return Err(AppError::Forbidden(format!(
+                "Flag set '{}' is protected, changes require approval",
+                name
+            )));
+        }
+        None => {}
+    }
+
+    let mut proposed = change(&current).map_err(AppError::BadRequest)?;
+    stamp_lifecycle(Some(&current), &mut proposed);
+    validate_flags(&state.schemas.load(), &proposed)
+        .inspect_err(|_| METRICS.record_validation_failure(name, "schema"))?;
+    lint_flags(&proposed, false)
+        .inspect_err(|_| METRICS.record_validation_failure(name, "semantic"))?;
+
+    write_flag_document(&file_path, &proposed)?;
+    state.search.update(name, &proposed);
+    Ok(proposed)
+}
@@ -0,0 +1,63 @@
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    error::{AppError, AppQuery, AppResult, ProblemDetails, PROBLEM_JSON},
+    handlers::api::flags::AppState,
+    search::{FlagType, SearchFilter, SearchResult},
+};
+
+/// Query parameters of the search endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchParams {
+    /// Whitespace separated terms that must all match a flag, case-insensitively
+    #[param(example = "ends_with @acme.com")]
+    pub q: String,
+    /// Only return flags in this state
+    #[param(example = "ENABLED")]
+    pub state: Option<String>,
+    /// Only return flags of this type
+    #[serde(rename = "type")]
+    pub flag_type: Option<FlagType>,
+}
+
+/// Response of the search endpoint
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchResponse {
+    /// Matching flags, ordered by flag set and flag
+    pub results: Vec<SearchResult>,
+}
+
+/// Search flags across all flag sets
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "Flags matching all terms", body = SearchResponse),
+        (status = 400, description = "Missing or empty query (`bad_request`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "flags"
+)]
+pub async fn search_flags(
+    State(state): State<AppState>,
+    AppQuery(params): AppQuery<SearchParams>,
+) -> AppResult<impl IntoResponse> {
+    if params.q.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "Query parameter 'q' must not be empty".to_string(),
+        ));
+    }
+
+    let filter = SearchFilter {
+        state: params.state.map(|state| state.to_uppercase()),
+        flag_type: params.flag_type,
+    };
+
+    Ok(Json(SearchResponse {
+        results: state.search.search(&params.q, &filter),
+    }))
+}
@@ -0,0 +1,163 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    error::{AppJson, ProblemDetails, PROBLEM_JSON},
+    handlers::api::AppState,
+    lint::{self, LintReport},
+    schema::SchemaRegistry,
+};
+
+/// A named flagd document submitted for validation
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NamedDocument {
+    /// Name used to identify the document in the report, e.g. the file name
+    #[schema(example = "production.flagd.json")]
+    pub name: String,
+    /// Complete flagd document including $schema
+    #[schema(value_type = Object)]
+    pub content: serde_json::Value,
+}
+
+/// Request payload for dry-run validation.
+///
+/// Either a single complete flagd document, or several named documents wrapped in
+/// a `documents` array.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum ValidateRequest {
+    /// Several named documents
+    Multiple {
+        /// Documents to validate
+        documents: Vec<NamedDocument>,
+    },
+    /// A single flagd document
+    #[schema(value_type = Object)]
+    Single(serde_json::Value),
+}
+
+/// A single JSON schema violation
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value within the document
+    #[schema(example = "/flags/new-checkout/state")]
+    pub pointer: String,
+    /// JSON pointer to the violated keyword within the schema
+    pub schema_path: String,
+    /// Human readable description of the violation
+    pub message: String,
+}
+
+/// Validation result for one document
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentReport {
+    /// Name of the document, if it was submitted with one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Whether the document passed schema validation and has no lint errors
+    pub valid: bool,
+    /// flagd schema version the document was validated against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "v0")]
+    pub schema_version: Option<String>,
+    /// All JSON schema violations of the document
+    pub schema_errors: Vec<SchemaViolation>,
+    /// Result of the semantic lint pass. Only run when the schema validation passes.
+    pub lint: LintReport,
+}
+
+/// Response of a dry-run validation
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidateResponse {
+    /// Whether every submitted document is valid
+    pub valid: bool,
+    /// Per document validation results, in submission order
+    pub documents: Vec<DocumentReport>,
+}
+
+/// Validate a single document against the schema version it declares and the
+/// semantic rules
+#[tracing::instrument(skip_all, fields(name = name.as_deref()))]
+pub(crate) fn validate_document(
+    schemas: &SchemaRegistry,
+    name: Option<String>,
+    content: &serde_json::Value,
+) -> DocumentReport {
+    let (schema_version, schema_errors) = match schemas.validator_for(content) {
+        Ok((version, schema)) => (
+            Some(version.to_string()),
+            schema
+                .iter_errors(content)
+                .map(|error| SchemaViolation {
+                    pointer: error.instance_path.as_str().to_string(),
+                    schema_path: error.schema_path.as_str().to_string(),
+                    message: error.to_string(),
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(error) => (
+            None,
+            vec![SchemaViolation {
+                pointer: "/$schema".to_string(),
+                schema_path: String::new(),
+                message: error.to_string(),
+            }],
+        ),
+    };
+
+    // The lint pass relies on the document shape guaranteed by the schema
+    let lint = if schema_errors.is_empty() {
+        lint::lint_document(content)
+    } else {
+        LintReport::default()
+    };
+
+    DocumentReport {
+        name,
+        valid: schema_errors.is_empty() && !lint.has_errors(),
+        schema_version,
+        schema_errors,
+        lint,
+    }
+}
+
+/// Validate flagd documents without writing anything
+#[utoipa::path(
+    post,
+    path = "/api/validate",
+    request_body = ValidateRequest,
+    responses(
+        (status = 200, description = "All documents are valid", body = ValidateResponse),
+        (status = 422, description = "At least one document is invalid", body = ValidateResponse),
+        (status = 400, description = "Malformed request (`bad_request`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 413, description = "Request body too large (`payload_too_large`)", body = ProblemDetails, content_type = PROBLEM_JSON),
+        (status = 415, description = "Request body is not JSON (`unsupported_media_type`)", body = ProblemDetails, content_type = PROBLEM_JSON)
+    ),
+    tag = "validation"
+)]
+pub async fn validate_documents(
+    State(state): State<AppState>,
+    AppJson(payload): AppJson<ValidateRequest>,
+) -> impl IntoResponse {
+    let schemas = state.schemas.load();
+
+    let documents = match payload {
+        ValidateRequest::Multiple { documents } => documents
+            .iter()
+            .map(|doc| validate_document(&schemas, Some(doc.name.clone()), &doc.content))
+            .collect::<Vec<_>>(),
+        ValidateRequest::Single(content) => vec![validate_document(&schemas, None, &content)],
+    };
+
+    let valid = documents.iter().all(|report| report.valid);
+    let status = if valid {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+
+    (status, Json(ValidateResponse { valid, documents }))
+}
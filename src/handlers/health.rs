@@ -1,32 +1,124 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{background::TaskStatus, handlers::api::AppState};
+
+/// File created and removed in FLAGS_DIR to check that it is writable
+const WRITE_PROBE_FILE: &str = ".flagd-ui-ready-check";
 
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 struct HealthResponse {
     status: String,
     message: String,
+    version: String,
+    uptime_seconds: u64,
+}
+
+#[derive(Serialize)]
+struct ReadinessResponse {
+    status: String,
+    components: BTreeMap<String, ComponentStatus>,
+}
+
+#[derive(Serialize)]
+struct ComponentStatus {
+    status: String,
+    message: String,
+}
+
+impl ComponentStatus {
+    fn from_result(result: Result<String, String>) -> Self {
+        match result {
+            Ok(message) => Self {
+                status: "ok".to_string(),
+                message,
+            },
+            Err(message) => Self {
+                status: "failed".to_string(),
+                message,
+            },
+        }
+    }
+
+    fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
 }
 
 /// GET /health - Basic liveness check
-pub async fn health_check() -> impl IntoResponse {
+pub async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
     (
         StatusCode::OK,
         Json(HealthResponse {
             status: "ok".to_string(),
             message: "Server is running".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_seconds: state.started_at.elapsed().as_secs(),
         }),
     )
 }
 
-/// GET /ready - Readiness check
-pub async fn readiness_check() -> impl IntoResponse {
-    // For now, just return OK. In the future, this can check database connections,
-    // external services, etc.
+/// GET /ready - Readiness check of the flags directory, schemas and background tasks.
+///
+/// Returns 503 if any component failed.
+pub async fn readiness_check(State(state): State<AppState>) -> impl IntoResponse {
+    let config = state.config.load();
+    let schemas = state.schemas.load();
+
+    let mut components = BTreeMap::new();
+    components.insert(
+        "flags_dir".to_string(),
+        ComponentStatus::from_result(check_flags_dir(Path::new(&config.flags_dir))),
+    );
+
+    let versions = schemas.versions().collect::<Vec<_>>();
+    components.insert(
+        "schemas".to_string(),
+        ComponentStatus::from_result(if versions.is_empty() {
+            Err("No schema version loaded".to_string())
+        } else {
+            Ok(format!(
+                "Loaded versions {} from {}",
+                versions.join(", "),
+                schemas.source()
+            ))
+        }),
+    );
+
+    for (name, status) in state.tasks.snapshot() {
+        let result = match status {
+            TaskStatus::Running => Ok("Running".to_string()),
+            TaskStatus::Failed(error) => Err(error),
+        };
+        components.insert(name, ComponentStatus::from_result(result));
+    }
+
+    let ready = components.values().all(ComponentStatus::is_ok);
+    let (status, label) = if ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+
     (
-        StatusCode::OK,
-        Json(HealthResponse {
-            status: "ready".to_string(),
-            message: "Server is ready to accept requests".to_string(),
+        status,
+        Json(ReadinessResponse {
+            status: label.to_string(),
+            components,
         }),
     )
 }
+
+/// Check that the flags directory can be listed and written to
+fn check_flags_dir(flags_dir: &Path) -> Result<String, String> {
+    fs::read_dir(flags_dir).map_err(|e| format!("Cannot read {}: {}", flags_dir.display(), e))?;
+
+    let probe = flags_dir.join(WRITE_PROBE_FILE);
+    fs::write(&probe, b"")
+        .and_then(|()| fs::remove_file(&probe))
+        .map_err(|e| format!("Cannot write to {}: {}", flags_dir.display(), e))?;
+
+    Ok(format!("{} is readable and writable", flags_dir.display()))
+}
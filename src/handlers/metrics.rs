@@ -0,0 +1,11 @@
+use axum::{extract::State, http::header::CONTENT_TYPE, response::IntoResponse};
+use std::path::Path;
+
+use crate::{handlers::api::AppState, metrics::METRICS};
+
+/// GET /metrics - Prometheus metrics in the text exposition format
+pub async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let body = METRICS.render(Path::new(&state.config.load().flags_dir));
+
+    ([(CONTENT_TYPE, prometheus::TEXT_FORMAT)], body)
+}
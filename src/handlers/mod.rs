@@ -1,5 +1,14 @@
+pub mod admin;
 pub mod api;
 pub mod health;
+pub mod metrics;
 
-pub use api::{create_flag, delete_flag, get_flag, init_app_state, list_flags, update_flag};
+pub use api::{
+    approve_change_request, cancel_schedule, convert_flags, copy_flag, create_flag, create_rollout,
+    create_schedule, delete_flag, diff_flag_sets, export_flags, get_change_request, get_flag,
+    import_flags, init_app_state, list_change_requests, list_flags, list_rollouts, list_schedules,
+    pause_rollout, promote_flags, reject_change_request, rename_flag, resume_rollout,
+    rollback_rollout, run_scheduler, search_flags, stale_report, update_flag, validate_documents,
+};
 pub use health::{health_check, readiness_check};
+pub use metrics::prometheus_metrics;
@@ -0,0 +1,461 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use crate::{
+    document::{flag_entries, flag_mut},
+    records::now,
+};
+
+/// Flag metadata key of the team or person responsible for the flag
+pub const OWNER: &str = "owner";
+/// Flag metadata key of the date the flag was created, maintained by the server
+pub const CREATED_AT: &str = "createdAt";
+/// Flag metadata key of the date after which the flag should be removed
+pub const EXPIRES_AT: &str = "expiresAt";
+/// Flag metadata key of the date the flag was last disabled, maintained by the server
+pub const DISABLED_AT: &str = "disabledAt";
+/// Flag metadata key of a link to the ticket tracking the flag
+pub const TICKET: &str = "ticket";
+
+/// Metadata keys holding `YYYY-MM-DD` dates
+pub const DATE_KEYS: [&str; 3] = [CREATED_AT, EXPIRES_AT, DISABLED_AT];
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Days since 1970-01-01 of a `YYYY-MM-DD` date, optionally followed by a time
+/// starting with `T` as in RFC 3339
+pub fn parse_date(value: &str) -> Option<i64> {
+    let date = match value.split_once('T') {
+        Some((date, _)) => date,
+        None => value,
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next().filter(|p| p.len() == 4)?.parse::<i64>().ok()?;
+    let month = parts.next().filter(|p| p.len() == 2)?.parse::<i64>().ok()?;
+    let day = parts.next().filter(|p| p.len() == 2)?.parse::<i64>().ok()?;
+
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day))
+}
+
+/// `YYYY-MM-DD` form of a number of days since 1970-01-01
+pub fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Year, month and day of a number of days since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // Inverse of `days_from_civil`, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Days since 1970-01-01 of the current date in UTC
+pub fn today() -> i64 {
+    (now() / SECONDS_PER_DAY) as i64
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Maintain the lifecycle dates of the flags in a document about to be written.
+///
+/// Flags that are not in the `previous` version of the flag set get a `createdAt`
+/// of today, and dates the client dropped are carried over from `previous`.
+/// `disabledAt` records when a flag became `DISABLED` and is removed once it is
+/// enabled again.
+pub fn stamp_lifecycle(previous: Option<&Value>, doc: &mut Value) {
+    let today = format_date(today());
+    let previous_flags = previous.map(flag_entries).unwrap_or_default();
+    let keys = flag_entries(doc)
+        .into_iter()
+        .map(|(key, _, _)| key)
+        .collect::<Vec<_>>();
+
+    for key in keys {
+        let old = previous_flags
+            .iter()
+            .find(|(old_key, _, _)| *old_key == key)
+            .map(|(_, flag, _)| *flag);
+        let Some(flag) = flag_mut(doc, &key).and_then(Value::as_object_mut) else {
+            continue;
+        };
+        let disabled = is_disabled(flag.get("state"));
+        let was_disabled = old.is_some_and(|old| is_disabled(old.get("state")));
+        let old_date = |name: &str| {
+            old.and_then(|old| old.pointer(&format!("/metadata/{}", name)))
+                .cloned()
+        };
+
+        let created_at = old_date(CREATED_AT);
+        let disabled_at = old_date(DISABLED_AT);
+        let had_metadata = flag.contains_key("metadata");
+        let metadata = flag
+            .entry("metadata")
+            .or_insert_with(|| Value::Object(Map::new()));
+        let Some(metadata) = metadata.as_object_mut() else {
+            continue;
+        };
+
+        if !metadata.contains_key(CREATED_AT) {
+            // Flags written before lifecycle metadata existed have no known creation date
+            let created_at = if old.is_some() {
+                created_at
+            } else {
+                Some(Value::String(today.clone()))
+            };
+            if let Some(created_at) = created_at {
+                metadata.insert(CREATED_AT.to_string(), created_at);
+            }
+        }
+
+        if !disabled {
+            metadata.remove(DISABLED_AT);
+        } else if !metadata.contains_key(DISABLED_AT) {
+            let disabled_at = if was_disabled {
+                disabled_at
+            } else {
+                Some(Value::String(today.clone()))
+            };
+            if let Some(disabled_at) = disabled_at {
+                metadata.insert(DISABLED_AT.to_string(), disabled_at);
+            }
+        }
+
+        if !had_metadata && metadata.is_empty() {
+            flag.remove("metadata");
+        }
+    }
+}
+
+/// Remove the dates maintained by the server, so [`stamp_lifecycle`] stamps the
+/// flags as new, e.g. in a copy of a flag set
+pub fn clear_lifecycle(doc: &mut Value) {
+    let keys = flag_entries(doc)
+        .into_iter()
+        .map(|(key, _, _)| key)
+        .collect::<Vec<_>>();
+
+    for key in keys {
+        let Some(metadata) = flag_mut(doc, &key)
+            .and_then(|flag| flag.get_mut("metadata"))
+            .and_then(Value::as_object_mut)
+        else {
+            continue;
+        };
+        metadata.remove(CREATED_AT);
+        metadata.remove(DISABLED_AT);
+    }
+}
+
+fn is_disabled(state: Option<&Value>) -> bool {
+    state.and_then(Value::as_str) == Some("DISABLED")
+}
+
+/// Why a flag is considered stale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum StaleReason {
+    /// The `expiresAt` date has passed
+    Expired,
+    /// The flag has been `DISABLED` for at least the requested number of days
+    Disabled,
+    /// The flag has a single variant, so it cannot change anything
+    SingleVariant,
+}
+
+/// A flag found by the stale-flag report
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleFlag {
+    /// Flag set containing the flag
+    #[schema(example = "prod")]
+    pub flag_set: String,
+    /// Key of the flag
+    #[schema(example = "new-checkout")]
+    pub flag: String,
+    pub reasons: Vec<StaleReason>,
+    /// Owner from the flag's metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "checkout-team")]
+    pub owner: Option<String>,
+    /// Ticket from the flag's metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "2026-01-31")]
+    pub expires_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "2025-11-02")]
+    pub disabled_at: Option<String>,
+}
+
+/// Stale flags of a flag set, as of the day `today` (in days since 1970-01-01)
+pub fn stale_flags(flag_set: &str, doc: &Value, today: i64, disabled_days: u32) -> Vec<StaleFlag> {
+    flag_entries(doc)
+        .into_iter()
+        .filter_map(|(key, flag, _)| {
+            let metadata = |name: &str| {
+                flag.pointer(&format!("/metadata/{}", name))
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            };
+            let expires_at = metadata(EXPIRES_AT);
+            let disabled_at = metadata(DISABLED_AT);
+
+            let mut reasons = Vec::new();
+            if expires_at
+                .as_deref()
+                .and_then(parse_date)
+                .is_some_and(|expires| expires < today)
+            {
+                reasons.push(StaleReason::Expired);
+            }
+            if is_disabled(flag.get("state"))
+                && disabled_at
+                    .as_deref()
+                    .and_then(parse_date)
+                    .is_some_and(|disabled| today - disabled >= i64::from(disabled_days))
+            {
+                reasons.push(StaleReason::Disabled);
+            }
+            if flag
+                .get("variants")
+                .and_then(Value::as_object)
+                .is_some_and(|variants| variants.len() == 1)
+            {
+                reasons.push(StaleReason::SingleVariant);
+            }
+
+            (!reasons.is_empty()).then(|| StaleFlag {
+                flag_set: flag_set.to_string(),
+                flag: key,
+                reasons,
+                owner: metadata(OWNER),
+                ticket: metadata(TICKET),
+                expires_at,
+                disabled_at,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc(flag: Value) -> Value {
+        json!({ "flags": { "a": flag } })
+    }
+
+    fn flag(state: &str, metadata: Option<Value>) -> Value {
+        let mut flag = json!({
+            "state": state,
+            "variants": { "on": true, "off": false },
+            "defaultVariant": "off",
+        });
+        if let Some(metadata) = metadata {
+            flag["metadata"] = metadata;
+        }
+        flag
+    }
+
+    fn stamped(previous: Option<Value>, flag: Value) -> Value {
+        let mut doc = doc(flag);
+        stamp_lifecycle(previous.as_ref(), &mut doc);
+        doc["flags"]["a"].clone()
+    }
+
+    fn date(value: &str) -> i64 {
+        parse_date(value).unwrap()
+    }
+
+    #[test]
+    fn new_flags_are_created_today() {
+        let stamped = stamped(None, flag("ENABLED", None));
+        assert_eq!(
+            stamped["metadata"],
+            json!({ CREATED_AT: format_date(today()) })
+        );
+    }
+
+    #[test]
+    fn carries_created_at_over_when_the_client_drops_it() {
+        let previous = doc(flag(
+            "ENABLED",
+            Some(json!({ CREATED_AT: "2025-01-01", OWNER: "team" })),
+        ));
+        let stamped = stamped(
+            Some(previous),
+            flag("ENABLED", Some(json!({ OWNER: "team" }))),
+        );
+        assert_eq!(
+            stamped["metadata"],
+            json!({ CREATED_AT: "2025-01-01", OWNER: "team" })
+        );
+    }
+
+    #[test]
+    fn flags_from_before_lifecycle_metadata_get_no_created_at() {
+        let previous = doc(flag("ENABLED", None));
+        let stamped = stamped(Some(previous), flag("ENABLED", None));
+        // Nor is an empty metadata object left behind
+        assert!(stamped.get("metadata").is_none());
+    }
+
+    #[test]
+    fn keeps_an_existing_empty_metadata_object() {
+        let previous = doc(flag("ENABLED", None));
+        let stamped = stamped(Some(previous), flag("ENABLED", Some(json!({}))));
+        assert_eq!(stamped["metadata"], json!({}));
+    }
+
+    #[test]
+    fn stamps_disabled_at_while_disabled() {
+        let created = json!({ CREATED_AT: "2025-01-01" });
+
+        let disabled = stamped(
+            Some(doc(flag("ENABLED", Some(created.clone())))),
+            flag("DISABLED", None),
+        );
+        assert_eq!(
+            disabled["metadata"],
+            json!({ CREATED_AT: "2025-01-01", DISABLED_AT: format_date(today()) })
+        );
+
+        let previous = doc(flag(
+            "DISABLED",
+            Some(json!({ CREATED_AT: "2025-01-01", DISABLED_AT: "2025-02-01" })),
+        ));
+        let still_disabled = stamped(Some(previous.clone()), flag("DISABLED", None));
+        assert_eq!(still_disabled["metadata"][DISABLED_AT], "2025-02-01");
+
+        let enabled = stamped(
+            Some(previous),
+            flag(
+                "ENABLED",
+                Some(json!({ CREATED_AT: "2025-01-01", DISABLED_AT: "2025-02-01" })),
+            ),
+        );
+        assert_eq!(enabled["metadata"], created);
+    }
+
+    #[test]
+    fn flags_disabled_before_disabled_at_existed_get_none() {
+        let previous = doc(flag("DISABLED", None));
+        let stamped = stamped(Some(previous), flag("DISABLED", None));
+        assert!(stamped.get("metadata").is_none());
+    }
+
+    #[test]
+    fn parse_date_knows_leap_years() {
+        assert!(parse_date("2024-02-29").is_some());
+        assert!(parse_date("2000-02-29").is_some());
+        assert_eq!(parse_date("1900-02-29"), None);
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-04-31"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("2024-1-01"), None);
+        assert_eq!(parse_date("2024-02-29T23:59:59Z"), parse_date("2024-02-29"));
+    }
+
+    #[test]
+    fn days_in_month_of_leap_and_common_years() {
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2023, 4), 30);
+        assert_eq!(days_in_month(2023, 12), 31);
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(format_date(date("2024-02-29") + 1), "2024-03-01");
+        assert_eq!(format_date(date("2023-12-31") + 1), "2024-01-01");
+
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert!((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day));
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn reports_flags_disabled_for_at_least_the_given_days() {
+        let doc = doc(flag(
+            "DISABLED",
+            Some(json!({ DISABLED_AT: "2025-01-01", OWNER: "team" })),
+        ));
+        let disabled = date("2025-01-01");
+
+        assert!(stale_flags("set", &doc, disabled + 29, 30).is_empty());
+
+        let stale = stale_flags("set", &doc, disabled + 30, 30);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].flag_set, "set");
+        assert_eq!(stale[0].reasons, [StaleReason::Disabled]);
+        assert_eq!(stale[0].owner.as_deref(), Some("team"));
+        assert_eq!(stale[0].disabled_at.as_deref(), Some("2025-01-01"));
+    }
+
+    #[test]
+    fn reports_flags_after_their_expiry_date() {
+        let doc = doc(flag("ENABLED", Some(json!({ EXPIRES_AT: "2025-06-30" }))));
+        let expires = date("2025-06-30");
+
+        assert!(stale_flags("set", &doc, expires, 30).is_empty());
+        assert_eq!(
+            stale_flags("set", &doc, expires + 1, 30)[0].reasons,
+            [StaleReason::Expired]
+        );
+    }
+
+    #[test]
+    fn reports_flags_with_a_single_variant() {
+        let doc = json!({ "flags": {
+            "a": { "state": "ENABLED", "variants": { "on": true }, "defaultVariant": "on" },
+            "b": flag("ENABLED", None),
+        } });
+
+        let stale = stale_flags("set", &doc, today(), 30);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].flag, "a");
+        assert_eq!(stale[0].reasons, [StaleReason::SingleVariant]);
+    }
+}
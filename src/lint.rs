@@ -0,0 +1,570 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use utoipa::ToSchema;
+
+use crate::{
+    document::{escape_pointer, flag_entries},
+    lifecycle::{self, DATE_KEYS, EXPIRES_AT},
+};
+
+/// Severity of a semantic lint finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    /// The document would misbehave in flagd and is rejected on write
+    Error,
+    /// The document is accepted but probably not what the author intended
+    Warning,
+}
+
+/// A single semantic lint finding
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LintIssue {
+    /// Severity of the finding
+    pub severity: LintSeverity,
+    /// Stable identifier of the check that produced the finding
+    #[schema(example = "unknown-default-variant")]
+    pub code: &'static str,
+    /// Key of the affected flag, if the finding belongs to a single flag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "new-checkout")]
+    pub flag: Option<String>,
+    /// JSON pointer to the offending value within the document
+    #[schema(example = "/flags/new-checkout/defaultVariant")]
+    pub pointer: String,
+    /// Human readable description of the finding
+    pub message: String,
+}
+
+/// Result of the semantic lint pass over a flagd document
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct LintReport {
+    /// Findings that make the document unusable by flagd
+    pub errors: Vec<LintIssue>,
+    /// Findings that are suspicious but do not block a write
+    pub warnings: Vec<LintIssue>,
+}
+
+impl LintReport {
+    /// Whether the report contains at least one error
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Combine all error messages into a single line, e.g. for error responses
+    pub fn error_summary(&self) -> String {
+        self.errors
+            .iter()
+            .map(|issue| format!("{} ({})", issue.message, issue.pointer))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    fn push(
+        &mut self,
+        severity: LintSeverity,
+        code: &'static str,
+        flag: Option<&str>,
+        pointer: String,
+        message: String,
+    ) {
+        let issue = LintIssue {
+            severity,
+            code,
+            flag: flag.map(str::to_string),
+            pointer,
+            message,
+        };
+
+        match severity {
+            LintSeverity::Error => self.errors.push(issue),
+            LintSeverity::Warning => self.warnings.push(issue),
+        }
+    }
+}
+
+/// Run the semantic checks on a complete flagd document.
+///
+/// The document is expected to have passed schema validation already; shapes the
+/// schema rejects are skipped silently instead of being reported twice.
+pub fn lint_document(doc: &Value) -> LintReport {
+    let mut report = LintReport::default();
+
+    let evaluators = doc.get("$evaluators").and_then(Value::as_object);
+    let mut used_evaluators = BTreeSet::new();
+
+    for (key, flag, pointer) in flag_entries(doc) {
+        let mut linter = FlagLinter {
+            key: &key,
+            variants: flag.get("variants").and_then(Value::as_object),
+            evaluators,
+            used_evaluators: &mut used_evaluators,
+            ref_stack: Vec::new(),
+            report: &mut report,
+        };
+        linter.lint_flag(flag, &pointer);
+    }
+
+    for name in evaluators.into_iter().flat_map(|map| map.keys()) {
+        if !used_evaluators.contains(name.as_str()) {
+            report.push(
+                LintSeverity::Warning,
+                "unused-evaluator",
+                None,
+                format!("/$evaluators/{}", escape_pointer(name)),
+                format!("Evaluator '{}' is not referenced by any flag", name),
+            );
+        }
+    }
+
+    report
+}
+
+/// Name of the JSON type of a variant value, used to detect mixed variant types
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+struct FlagLinter<'a> {
+    key: &'a str,
+    variants: Option<&'a serde_json::Map<String, Value>>,
+    evaluators: Option<&'a serde_json::Map<String, Value>>,
+    used_evaluators: &'a mut BTreeSet<String>,
+    /// Evaluators currently being expanded, to stop on circular `$ref`s
+    ref_stack: Vec<String>,
+    report: &'a mut LintReport,
+}
+
+impl FlagLinter<'_> {
+    fn lint_flag(&mut self, flag: &Value, pointer: &str) {
+        if let Some(variants) = self.variants {
+            let kinds = variants.values().map(value_kind).collect::<BTreeSet<_>>();
+            if kinds.len() > 1 {
+                self.error(
+                    "mixed-variant-types",
+                    format!("{}/variants", pointer),
+                    format!(
+                        "Flag '{}' mixes variant value types ({})",
+                        self.key,
+                        kinds.into_iter().collect::<Vec<_>>().join(", ")
+                    ),
+                );
+            }
+        }
+
+        if let Some(default_variant) = flag.get("defaultVariant").and_then(Value::as_str) {
+            if !self.has_variant(default_variant) {
+                self.error(
+                    "unknown-default-variant",
+                    format!("{}/defaultVariant", pointer),
+                    format!(
+                        "Default variant '{}' of flag '{}' is not defined in its variants",
+                        default_variant, self.key
+                    ),
+                );
+            }
+        }
+
+        if let Some(targeting) = flag.get("targeting") {
+            self.walk(targeting, &format!("{}/targeting", pointer), true);
+        }
+
+        if let Some(metadata) = flag.get("metadata").and_then(Value::as_object) {
+            self.lint_lifecycle(metadata, &format!("{}/metadata", pointer));
+        }
+    }
+
+    /// Lifecycle dates must be `YYYY-MM-DD` dates for the stale-flag report to use them
+    fn lint_lifecycle(&mut self, metadata: &serde_json::Map<String, Value>, pointer: &str) {
+        for key in DATE_KEYS {
+            let Some(value) = metadata.get(key) else {
+                continue;
+            };
+            let date = value.as_str().and_then(lifecycle::parse_date);
+            let date_pointer = format!("{}/{}", pointer, key);
+
+            match date {
+                None => self.warning(
+                    "invalid-lifecycle-date",
+                    date_pointer,
+                    format!(
+                        "Metadata '{}' of flag '{}' is not a YYYY-MM-DD date",
+                        key, self.key
+                    ),
+                ),
+                Some(date) if key == EXPIRES_AT && date < lifecycle::today() => self.warning(
+                    "flag-expired",
+                    date_pointer,
+                    format!(
+                        "Flag '{}' expired on {}",
+                        self.key,
+                        lifecycle::format_date(date)
+                    ),
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn has_variant(&self, name: &str) -> bool {
+        self.variants.is_some_and(|v| v.contains_key(name))
+    }
+
+    /// Walk a JsonLogic rule. `as_result` is set when the value of the expression is
+    /// used as the variant name of the flag.
+    fn walk(&mut self, rule: &Value, pointer: &str, as_result: bool) {
+        match rule {
+            Value::String(name) if as_result => self.check_variant(name, pointer),
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.walk(item, &format!("{}/{}", pointer, index), false);
+                }
+            }
+            Value::Object(ops) => {
+                for (op, args) in ops {
+                    let op_pointer = format!("{}/{}", pointer, escape_pointer(op));
+                    match op.as_str() {
+                        "$ref" => self.walk_ref(args, &op_pointer, as_result),
+                        "if" => self.walk_if(args, &op_pointer, as_result),
+                        "fractional" => self.walk_fractional(args, &op_pointer, as_result),
+                        _ => self.walk(args, &op_pointer, false),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn walk_ref(&mut self, args: &Value, pointer: &str, as_result: bool) {
+        let Some(name) = args.as_str() else {
+            return;
+        };
+
+        let Some(evaluator) = self.evaluators.and_then(|map| map.get(name)) else {
+            self.error(
+                "unknown-evaluator",
+                pointer.to_string(),
+                format!(
+                    "Flag '{}' references evaluator '{}' which is not defined in $evaluators",
+                    self.key, name
+                ),
+            );
+            return;
+        };
+
+        self.used_evaluators.insert(name.to_string());
+
+        if self.ref_stack.iter().any(|entry| entry == name) {
+            self.error(
+                "circular-evaluator",
+                pointer.to_string(),
+                format!("Evaluator '{}' references itself", name),
+            );
+            return;
+        }
+
+        self.ref_stack.push(name.to_string());
+        self.walk(
+            evaluator,
+            &format!("/$evaluators/{}", escape_pointer(name)),
+            as_result,
+        );
+        self.ref_stack.pop();
+    }
+
+    /// `if` takes `[cond, then, cond, then, ..., else]`; the branches are results
+    fn walk_if(&mut self, args: &Value, pointer: &str, as_result: bool) {
+        let Some(items) = args.as_array() else {
+            self.walk(args, pointer, false);
+            return;
+        };
+
+        for (index, item) in items.iter().enumerate() {
+            let is_branch = index % 2 == 1 || index == items.len() - 1 && index > 0;
+            self.walk(
+                item,
+                &format!("{}/{}", pointer, index),
+                as_result && is_branch,
+            );
+        }
+    }
+
+    /// `fractional` takes an optional bucketing expression followed by
+    /// `[variant, weight]` buckets
+    fn walk_fractional(&mut self, args: &Value, pointer: &str, as_result: bool) {
+        let Some(items) = args.as_array() else {
+            return;
+        };
+
+        let mut total = 0.0;
+        let mut all_weighted = true;
+        let mut has_buckets = false;
+
+        for (index, item) in items.iter().enumerate() {
+            let item_pointer = format!("{}/{}", pointer, index);
+            let Some(bucket) = item.as_array() else {
+                // Bucketing expression
+                self.walk(item, &item_pointer, false);
+                continue;
+            };
+
+            has_buckets = true;
+            if let Some(name) = bucket.first().and_then(Value::as_str) {
+                if as_result {
+                    self.check_variant(name, &format!("{}/0", item_pointer));
+                }
+            }
+            match bucket.get(1).and_then(Value::as_f64) {
+                Some(weight) => total += weight,
+                None => all_weighted = false,
+            }
+        }
+
+        if has_buckets && all_weighted && (total - 100.0).abs() > 1e-9 {
+            self.warning(
+                "fractional-weights",
+                pointer.to_string(),
+                format!(
+                    "Fractional buckets of flag '{}' sum to {} instead of 100",
+                    self.key, total
+                ),
+            );
+        }
+    }
+
+    fn check_variant(&mut self, name: &str, pointer: &str) {
+        if !self.has_variant(name) {
+            self.error(
+                "unknown-targeting-variant",
+                pointer.to_string(),
+                format!(
+                    "Targeting of flag '{}' returns variant '{}' which is not defined in its variants",
+                    self.key, name
+                ),
+            );
+        }
+    }
+
+    fn error(&mut self, code: &'static str, pointer: String, message: String) {
+        self.report
+            .push(LintSeverity::Error, code, Some(self.key), pointer, message);
+    }
+
+    fn warning(&mut self, code: &'static str, pointer: String, message: String) {
+        self.report.push(
+            LintSeverity::Warning,
+            code,
+            Some(self.key),
+            pointer,
+            message,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Codes of all findings, errors first
+    fn codes(doc: &Value) -> Vec<&'static str> {
+        let report = lint_document(doc);
+        report
+            .errors
+            .iter()
+            .chain(&report.warnings)
+            .map(|issue| issue.code)
+            .collect()
+    }
+
+    fn boolean_flag(targeting: Value) -> Value {
+        json!({
+            "state": "ENABLED",
+            "variants": { "on": true, "off": false },
+            "defaultVariant": "off",
+            "targeting": targeting,
+        })
+    }
+
+    #[test]
+    fn accepts_a_consistent_document() {
+        let doc = json!({ "flags": { "a": boolean_flag(json!({})) } });
+        assert!(codes(&doc).is_empty());
+    }
+
+    #[test]
+    fn mixed_variant_types() {
+        let doc = json!({ "flags": { "a": {
+            "state": "ENABLED",
+            "variants": { "on": true, "off": "no" },
+            "defaultVariant": "off",
+        } } });
+        assert_eq!(codes(&doc), ["mixed-variant-types"]);
+    }
+
+    #[test]
+    fn unknown_default_variant() {
+        let doc = json!({ "flags": { "a": {
+            "state": "ENABLED",
+            "variants": { "on": true },
+            "defaultVariant": "off",
+        } } });
+        let report = lint_document(&doc);
+        assert_eq!(report.errors[0].code, "unknown-default-variant");
+        assert_eq!(report.errors[0].pointer, "/flags/a/defaultVariant");
+    }
+
+    #[test]
+    fn unknown_targeting_variant() {
+        let known = json!({ "flags": { "a": boolean_flag(json!({ "if": [true, "on"] })) } });
+        assert!(codes(&known).is_empty());
+
+        let unknown = json!({ "flags": { "a": boolean_flag(json!({ "if": [true, "maybe"] })) } });
+        let report = lint_document(&unknown);
+        assert_eq!(report.errors[0].code, "unknown-targeting-variant");
+        assert_eq!(report.errors[0].pointer, "/flags/a/targeting/if/1");
+    }
+
+    #[test]
+    fn if_conditions_are_not_variants() {
+        // Only the branches and the final else are results, `"x"` and `"y"` are values
+        let doc = json!({ "flags": { "a": boolean_flag(json!({
+            "if": [{ "==": [{ "var": "tier" }, "x"] }, "on", "y", "maybe", "on"]
+        })) } });
+        assert_eq!(codes(&doc), ["unknown-targeting-variant"]);
+        assert_eq!(
+            lint_document(&doc).errors[0].pointer,
+            "/flags/a/targeting/if/3"
+        );
+
+        let doc = json!({ "flags": { "a": boolean_flag(json!({
+            "if": [{ "==": [{ "var": "tier" }, "x"] }, "on", "off"]
+        })) } });
+        assert!(codes(&doc).is_empty());
+    }
+
+    #[test]
+    fn array_form_flags_use_index_pointers() {
+        let doc = json!({ "flags": [{
+            "key": "a",
+            "state": "ENABLED",
+            "variants": { "on": true },
+            "defaultVariant": "off",
+        }] });
+        let report = lint_document(&doc);
+        assert_eq!(report.errors[0].flag.as_deref(), Some("a"));
+        assert_eq!(report.errors[0].pointer, "/flags/0/defaultVariant");
+    }
+
+    #[test]
+    fn unknown_evaluator() {
+        let defined = json!({
+            "flags": { "a": boolean_flag(json!({ "if": [{ "$ref": "beta" }, "on", "off"] })) },
+            "$evaluators": { "beta": { "in": [{ "var": "email" }, ["x@y.z"]] } },
+        });
+        assert!(codes(&defined).is_empty());
+
+        let missing = json!({
+            "flags": { "a": boolean_flag(json!({ "if": [{ "$ref": "beta" }, "on", "off"] })) },
+        });
+        assert_eq!(codes(&missing), ["unknown-evaluator"]);
+    }
+
+    #[test]
+    fn evaluator_results_are_checked_as_variants() {
+        let doc = json!({
+            "flags": { "a": boolean_flag(json!({ "$ref": "pick" })) },
+            "$evaluators": { "pick": { "if": [true, "on", "nope"] } },
+        });
+        let report = lint_document(&doc);
+        assert_eq!(report.errors[0].code, "unknown-targeting-variant");
+        assert_eq!(report.errors[0].pointer, "/$evaluators/pick/if/2");
+    }
+
+    #[test]
+    fn unused_evaluator() {
+        let doc = json!({
+            "flags": { "a": boolean_flag(json!({})) },
+            "$evaluators": { "beta": true },
+        });
+        let report = lint_document(&doc);
+        assert_eq!(codes(&doc), ["unused-evaluator"]);
+        assert_eq!(report.warnings[0].flag, None);
+    }
+
+    #[test]
+    fn circular_evaluator() {
+        let acyclic = json!({
+            "flags": { "a": boolean_flag(json!({ "if": [{ "$ref": "outer" }, "on", "off"] })) },
+            "$evaluators": {
+                "outer": { "and": [{ "$ref": "inner" }, true] },
+                "inner": true,
+            },
+        });
+        assert!(codes(&acyclic).is_empty());
+
+        let cyclic = json!({
+            "flags": { "a": boolean_flag(json!({ "if": [{ "$ref": "outer" }, "on", "off"] })) },
+            "$evaluators": {
+                "outer": { "and": [{ "$ref": "inner" }, true] },
+                "inner": { "or": [{ "$ref": "outer" }, false] },
+            },
+        });
+        assert_eq!(codes(&cyclic), ["circular-evaluator"]);
+    }
+
+    #[test]
+    fn fractional_weights() {
+        let exact = json!({ "flags": { "a": boolean_flag(json!({
+            "fractional": [{ "var": "email" }, ["on", 25], ["off", 75]]
+        })) } });
+        assert!(codes(&exact).is_empty());
+
+        let short = json!({ "flags": { "a": boolean_flag(json!({
+            "fractional": [["on", 25], ["off", 70]]
+        })) } });
+        assert_eq!(codes(&short), ["fractional-weights"]);
+
+        // Without weights flagd splits evenly
+        let even = json!({ "flags": { "a": boolean_flag(json!({
+            "fractional": [["on"], ["off"]]
+        })) } });
+        assert!(codes(&even).is_empty());
+    }
+
+    #[test]
+    fn fractional_variants() {
+        let doc = json!({ "flags": { "a": boolean_flag(json!({
+            "fractional": [["on", 50], ["maybe", 50]]
+        })) } });
+        let report = lint_document(&doc);
+        assert_eq!(report.errors[0].code, "unknown-targeting-variant");
+        assert_eq!(
+            report.errors[0].pointer,
+            "/flags/a/targeting/fractional/1/0"
+        );
+    }
+
+    #[test]
+    fn lifecycle_dates() {
+        let mut flag = boolean_flag(json!({}));
+        flag["metadata"] = json!({ "createdAt": "2024-01-31", "expiresAt": "9999-12-31" });
+        assert!(codes(&json!({ "flags": { "a": flag } })).is_empty());
+
+        let mut flag = boolean_flag(json!({}));
+        flag["metadata"] = json!({ "createdAt": "31/01/2024", "expiresAt": "2000-01-01" });
+        assert_eq!(
+            codes(&json!({ "flags": { "a": flag } })),
+            ["invalid-lifecycle-date", "flag-expired"]
+        );
+    }
+}
@@ -1,67 +1,135 @@
+mod actor;
+mod archive;
+mod background;
+mod change_requests;
+mod cli;
 mod config;
+mod convert;
+mod diff;
+mod document;
 mod error;
 mod handlers;
+mod lifecycle;
+mod lint;
+mod metrics;
 mod middleware;
 mod openapi_doc;
+mod records;
+mod rollouts;
+mod schedules;
+mod schema;
+mod search;
+mod server;
+mod telemetry;
+mod tls;
 
-use axum::{routing::get, Router};
+use axum::{
+    middleware::{from_fn, from_fn_with_state},
+    routing::{delete, get, post},
+    Router,
+};
+use clap::Parser;
+use std::process::ExitCode;
 use tower_http::{
     compression::CompressionLayer,
     cors::CorsLayer,
     services::{ServeDir, ServeFile},
     trace::TraceLayer,
 };
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+use cli::{Cli, Command};
 use config::ServerConfig;
 use handlers::{
-    create_flag, delete_flag, get_flag, health_check, init_app_state, list_flags, readiness_check,
-    update_flag,
+    approve_change_request, cancel_schedule, convert_flags, copy_flag, create_flag, create_rollout,
+    create_schedule, delete_flag, diff_flag_sets, export_flags, get_change_request, get_flag,
+    health_check, import_flags, init_app_state, list_change_requests, list_flags, list_rollouts,
+    list_schedules, pause_rollout, prometheus_metrics, promote_flags, readiness_check,
+    reject_change_request, rename_flag, resume_rollout, rollback_rollout, run_scheduler,
+    search_flags, stale_report, update_flag, validate_documents,
 };
 use openapi_doc::ApiDoc;
+use tls::TlsState;
 
 #[tokio::main]
-async fn main() {
-    // Initialize tracing for structured logging
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
 
     // Load configuration
-    let config = ServerConfig::from_env();
-    let addr = format!("0.0.0.0:{}", config.port);
+    let config = match ServerConfig::load(cli.config.into_sources()) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return ExitCode::from(cli::EXIT_ERROR);
+        }
+    };
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            serve(config).await;
+            ExitCode::SUCCESS
+        }
+        command => command.run(&config),
+    }
+}
+
+/// Run the web server until it is shut down
+async fn serve(config: ServerConfig) {
+    // Initialize structured logging and optional OpenTelemetry export
+    let telemetry = telemetry::init(config.log_format);
 
-    tracing::info!("Starting server with config: {:?}", config);
+    tracing::info!("Starting server with config: {}", config.redacted());
 
     // Initialize application state with schema validation
     let app_state = init_app_state(config.clone())
         .await
         .expect("Failed to initialize application state");
 
+    let schemas = app_state.schemas.load();
     tracing::info!(
-        "Schema validation initialized from: {}",
-        config.schema_file_path
+        "Schema validation initialized from {} for versions: {}",
+        schemas.source(),
+        schemas.versions().collect::<Vec<_>>().join(", ")
     );
 
+    // Load TLS certificates before accepting connections
+    let tls = TlsState::load(&config).expect("Failed to load TLS certificates");
+
+    // Reload configuration and schemas on SIGHUP
+    #[cfg(unix)]
+    app_state.tasks.spawn(
+        "sighup_reload",
+        handlers::admin::reload_on_sighup(app_state.clone()),
+    );
+
+    // Apply scheduled changes and rollout steps when they are due
+    app_state
+        .tasks
+        .spawn("scheduler", run_scheduler(app_state.clone()));
+
     // Build the application router
+    let tasks = app_state.tasks.clone();
     let app = create_router(&config, app_state);
 
-    // Create TCP listener
-    let listener = tokio::net::TcpListener::bind(&addr)
+    // Bind the TCP address or Unix socket
+    let listener = server::Listener::bind(&config)
         .await
         .expect("Failed to bind to address");
 
-    tracing::info!("Server listening on {}", addr);
-
     // Start the server
-    axum::serve(listener, app)
-        .await
-        .expect("Server failed to start");
+    match &tls {
+        Some(tls) => {
+            tracing::info!("Server listening on {} (HTTPS)", listener);
+            tasks.spawn("tls_certificate_watcher", tls.clone().watch());
+        }
+        None => tracing::info!("Server listening on {}", listener),
+    }
+
+    server::serve(listener, app, tls, config.shutdown_timeout()).await;
+
+    // Flush spans and logs that are still buffered
+    let _ = tokio::task::spawn_blocking(move || telemetry.shutdown()).await;
 }
 
 /// Create the Axum router with all routes and middleware
@@ -74,15 +142,63 @@ fn create_router(config: &ServerConfig, app_state: handlers::api::AppState) -> R
             "/flags/:name",
             get(get_flag).put(update_flag).delete(delete_flag),
         )
-        .with_state(app_state);
+        .route("/flags/:name/rename", post(rename_flag))
+        .route("/flags/:name/copy", post(copy_flag))
+        // Changes applied at a future time
+        .route(
+            "/flags/:name/schedules",
+            get(list_schedules).post(create_schedule),
+        )
+        .route("/flags/:name/schedules/:id", delete(cancel_schedule))
+        // Progressive rollouts
+        .route(
+            "/flags/:name/rollouts",
+            get(list_rollouts).post(create_rollout),
+        )
+        .route("/flags/:name/rollouts/:id/pause", post(pause_rollout))
+        .route("/flags/:name/rollouts/:id/resume", post(resume_rollout))
+        .route("/flags/:name/rollouts/:id/rollback", post(rollback_rollout))
+        // Promotion of flags between flag sets
+        .route("/promote", post(promote_flags))
+        // Flag-aware difference between flag sets
+        .route("/diff", get(diff_flag_sets))
+        // Change requests for protected flag sets
+        .route("/change-requests", get(list_change_requests))
+        .route("/change-requests/:id", get(get_change_request))
+        .route("/change-requests/:id/approve", post(approve_change_request))
+        .route("/change-requests/:id/reject", post(reject_change_request))
+        // Bulk export and import of flag sets
+        .route("/export", get(export_flags))
+        .route("/import", post(import_flags))
+        // Conversion of exports from other feature flag systems
+        .route("/convert", post(convert_flags))
+        // Search across all flag sets
+        .route("/search", get(search_flags))
+        // Reports across all flag sets
+        .route("/reports/stale", get(stale_report))
+        // Dry-run validation endpoint
+        .route("/validate", post(validate_documents))
+        .with_state(app_state.clone());
+
+    // Administrative endpoints, protected by ADMIN_TOKEN
+    let admin_routes = Router::new()
+        .route("/reload", post(handlers::admin::reload))
+        .with_state(app_state.clone());
 
     // Main application router
-    Router::new()
-        // Health check endpoints
+    // Health check endpoints
+    let health_routes = Router::new()
         .route("/health", get(health_check))
         .route("/ready", get(readiness_check))
+        // Prometheus metrics
+        .route("/metrics", get(prometheus_metrics))
+        .with_state(app_state.clone());
+
+    Router::new()
+        .merge(health_routes)
         // Mount API routes under /api prefix
         .nest("/api", api_routes)
+        .nest("/admin", admin_routes)
         // Swagger UI for interactive API documentation
         .merge(SwaggerUi::new("/swagger-ui").url("/api/openapi.json", ApiDoc::openapi()))
         // Serve static files from the public directory
@@ -95,5 +211,15 @@ fn create_router(config: &ServerConfig, app_state: handlers::api::AppState) -> R
         // Add middleware stack
         .layer(CorsLayer::permissive())
         .layer(CompressionLayer::new())
-        .layer(TraceLayer::new_for_http())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::make_request_span)
+                .on_response(telemetry::on_response),
+        )
+        .layer(from_fn_with_state(
+            app_state,
+            middleware::strip_untrusted_actor,
+        ))
+        .layer(from_fn(middleware::track_request_metrics))
+        .layer(from_fn(middleware::propagate_request_id))
 }
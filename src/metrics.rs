@@ -0,0 +1,184 @@
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use std::{
+    fs,
+    path::Path,
+    sync::LazyLock,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::document::flag_entries;
+
+/// Prometheus metrics of the server, exposed on `/metrics`
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    validation_failures: IntCounterVec,
+    storage_errors: IntCounterVec,
+    flag_sets: IntGauge,
+    flags: GaugeVec,
+    flag_set_last_modified: GaugeVec,
+}
+
+/// Process-wide metrics, shared by the HTTP handlers and the storage helpers
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("flagd_ui".to_string()), None)
+            .expect("metric prefix is valid");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .expect("metric is valid");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route and status",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("metric is valid");
+        let validation_failures = IntCounterVec::new(
+            Opts::new(
+                "validation_failures_total",
+                "Rejected flag set writes by flag set and validation stage",
+            ),
+            &["flag_set", "stage"],
+        )
+        .expect("metric is valid");
+        let storage_errors = IntCounterVec::new(
+            Opts::new(
+                "storage_errors_total",
+                "Failed flag set storage operations by operation",
+            ),
+            &["operation"],
+        )
+        .expect("metric is valid");
+        let flag_sets = IntGauge::new("flag_sets", "Number of flag sets in the flags directory")
+            .expect("metric is valid");
+        let flags = GaugeVec::new(
+            Opts::new("flags", "Number of flags by flag set"),
+            &["flag_set"],
+        )
+        .expect("metric is valid");
+        let flag_set_last_modified = GaugeVec::new(
+            Opts::new(
+                "flag_set_last_modified_timestamp_seconds",
+                "Last modification time of each flag set file",
+            ),
+            &["flag_set"],
+        )
+        .expect("metric is valid");
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration.clone()),
+            Box::new(validation_failures.clone()),
+            Box::new(storage_errors.clone()),
+            Box::new(flag_sets.clone()),
+            Box::new(flags.clone()),
+            Box::new(flag_set_last_modified.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            validation_failures,
+            storage_errors,
+            flag_sets,
+            flags,
+            flag_set_last_modified,
+        }
+    }
+
+    /// Count a finished HTTP request
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration
+            .with_label_values(&labels)
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Count a flag set write rejected by schema (`schema`) or semantic (`semantic`) validation
+    pub fn record_validation_failure(&self, flag_set: &str, stage: &str) {
+        self.validation_failures
+            .with_label_values(&[flag_set, stage])
+            .inc();
+    }
+
+    /// Count a failed `read`, `write`, `rename`, `delete` or `list` storage operation
+    pub fn record_storage_error(&self, operation: &str) {
+        self.storage_errors.with_label_values(&[operation]).inc();
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    ///
+    /// The flag set gauges are refreshed from the flags directory on every scrape.
+    pub fn render(&self, flags_dir: &Path) -> String {
+        self.refresh_flag_sets(flags_dir);
+
+        let mut buffer = Vec::new();
+        if let Err(error) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!(error = %error, "Failed to encode metrics");
+        }
+
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    fn refresh_flag_sets(&self, flags_dir: &Path) {
+        self.flags.reset();
+        self.flag_set_last_modified.reset();
+
+        let Ok(entries) = fs::read_dir(flags_dir) else {
+            self.flag_sets.set(0);
+            return;
+        };
+
+        let mut count = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".flagd.json"))
+            else {
+                continue;
+            };
+            count += 1;
+
+            if let Some(modified) = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            {
+                self.flag_set_last_modified
+                    .with_label_values(&[name])
+                    .set(modified.as_secs_f64());
+            }
+
+            let flag_count = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                .map(|doc| flag_entries(&doc).len())
+                .unwrap_or_default();
+            self.flags.with_label_values(&[name]).set(flag_count as f64);
+        }
+
+        self.flag_sets.set(count);
+    }
+}
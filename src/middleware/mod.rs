@@ -1 +1,7 @@
-// Placeholder for future custom middleware
+mod request_id;
+mod request_metrics;
+mod trusted_proxy;
+
+pub use request_id::{propagate_request_id, X_REQUEST_ID};
+pub use request_metrics::track_request_metrics;
+pub use trusted_proxy::strip_untrusted_actor;
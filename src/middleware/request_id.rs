@@ -0,0 +1,42 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::REQUEST_ID;
+
+/// Header carrying the request id in requests and responses
+pub static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest request id accepted from clients
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Give every request an id and echo it in the `X-Request-Id` response header.
+///
+/// A well-formed `X-Request-Id` sent by the client or a proxy is kept, otherwise a
+/// UUID is generated. The id is available to error responses through [`REQUEST_ID`].
+pub async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| {
+            !value.is_empty()
+                && value.len() <= MAX_REQUEST_ID_LEN
+                && value.bytes().all(|byte| byte.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let header = HeaderValue::from_str(&request_id).expect("request id is visible ASCII");
+    request
+        .headers_mut()
+        .insert(X_REQUEST_ID.clone(), header.clone());
+
+    let mut response = REQUEST_ID.scope(request_id, next.run(request)).await;
+    response.headers_mut().insert(X_REQUEST_ID.clone(), header);
+
+    response
+}
@@ -0,0 +1,33 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+
+use crate::metrics::METRICS;
+
+/// Count requests and their latency by route template and status.
+///
+/// Requests that did not match an API route (static files, unknown paths) share the
+/// `static` route label to keep the number of series bounded.
+pub async fn track_request_metrics(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "static".to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    METRICS.observe_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+
+    response
+}
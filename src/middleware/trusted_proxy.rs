@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    actor::{ClientAddr, ACTOR_HEADER},
+    handlers::api::AppState,
+};
+
+/// Drop the `X-Forwarded-User` header of requests that do not come from one of the
+/// `trusted_proxies`, so any client cannot claim to be any user.
+///
+/// Without a trusted identity, writes to protected flag sets and reviews of change
+/// requests are refused.
+pub async fn strip_untrusted_actor(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if request.headers().contains_key(ACTOR_HEADER) {
+        let client = request.extensions().get::<ClientAddr>().copied();
+        let trusted = client.is_some_and(|client| state.config.load().trusts_proxy(client));
+
+        if !trusted {
+            tracing::debug!(
+                client = ?client,
+                "Ignoring {} header of a client that is not a trusted proxy",
+                ACTOR_HEADER
+            );
+            request.headers_mut().remove(ACTOR_HEADER);
+        }
+    }
+
+    next.run(request).await
+}
@@ -9,17 +9,96 @@ use utoipa::OpenApi;
         crate::handlers::api::flags::create_flag,
         crate::handlers::api::flags::update_flag,
         crate::handlers::api::flags::delete_flag,
+        crate::handlers::api::flags::rename_flag,
+        crate::handlers::api::flags::copy_flag,
+        crate::handlers::api::promote::promote_flags,
+        crate::handlers::api::diff::diff_flag_sets,
+        crate::handlers::api::change_requests::list_change_requests,
+        crate::handlers::api::change_requests::get_change_request,
+        crate::handlers::api::change_requests::approve_change_request,
+        crate::handlers::api::change_requests::reject_change_request,
+        crate::handlers::api::schedules::create_schedule,
+        crate::handlers::api::schedules::list_schedules,
+        crate::handlers::api::schedules::cancel_schedule,
+        crate::handlers::api::rollouts::create_rollout,
+        crate::handlers::api::rollouts::list_rollouts,
+        crate::handlers::api::rollouts::pause_rollout,
+        crate::handlers::api::rollouts::resume_rollout,
+        crate::handlers::api::rollouts::rollback_rollout,
+        crate::handlers::api::export::export_flags,
+        crate::handlers::api::import::import_flags,
+        crate::handlers::api::convert::convert_flags,
+        crate::handlers::api::reports::stale_report,
+        crate::handlers::api::search::search_flags,
+        crate::handlers::api::validate::validate_documents,
+        crate::handlers::admin::reload,
     ),
     components(
         schemas(
             crate::handlers::api::CreateFlagRequest,
             crate::handlers::api::UpdateFlagRequest,
+            crate::handlers::api::RenameFlagRequest,
+            crate::handlers::api::CopyFlagRequest,
+            crate::handlers::api::PromoteRequest,
+            crate::handlers::api::PromoteResponse,
+            crate::handlers::api::ListChangeRequestsResponse,
+            crate::handlers::api::ReviewRequest,
+            crate::change_requests::ChangeRequest,
+            crate::change_requests::ChangeRequestStatus,
+            crate::handlers::api::CreateScheduleRequest,
+            crate::handlers::api::ListSchedulesResponse,
+            crate::schedules::Schedule,
+            crate::schedules::ScheduleStatus,
+            crate::schedules::ScheduledChange,
+            crate::handlers::api::CreateRolloutRequest,
+            crate::handlers::api::ListRolloutsResponse,
+            crate::rollouts::Rollout,
+            crate::rollouts::RolloutStatus,
+            crate::handlers::api::StaleReport,
+            crate::archive::Manifest,
+            crate::archive::ManifestEntry,
+            crate::handlers::api::ImportResponse,
+            crate::handlers::api::ImportFileReport,
+            crate::handlers::api::ImportStatus,
+            crate::handlers::api::ConvertResponse,
+            crate::convert::ConvertSource,
+            crate::convert::UntranslatedRule,
+            crate::handlers::api::SearchResponse,
+            crate::search::SearchResult,
+            crate::search::SearchMatch,
+            crate::search::SearchField,
+            crate::search::FlagType,
+            crate::lifecycle::StaleFlag,
+            crate::lifecycle::StaleReason,
+            crate::diff::FlagSetDiff,
+            crate::diff::FlagDiff,
+            crate::diff::FieldChange,
+            crate::diff::ChangeKind,
             crate::handlers::api::FlagDefinitionResponse,
             crate::handlers::api::ListFlagsResponse,
+            crate::handlers::api::FlagSetSummary,
+            crate::handlers::api::ValidationStatus,
+            crate::handlers::api::ValidateRequest,
+            crate::handlers::api::ValidateResponse,
+            crate::handlers::api::NamedDocument,
+            crate::handlers::api::DocumentReport,
+            crate::handlers::api::SchemaViolation,
+            crate::handlers::admin::ReloadResponse,
+            crate::lint::LintReport,
+            crate::lint::LintIssue,
+            crate::lint::LintSeverity,
+            crate::error::ProblemDetails,
+            crate::error::ErrorCode,
         )
     ),
     tags(
-        (name = "flags", description = "Feature Flag Definition Management API")
+        (name = "flags", description = "Feature Flag Definition Management API"),
+        (name = "change-requests", description = "Approval of changes to protected flag sets"),
+        (name = "schedules", description = "Changes to flag sets applied at a future time"),
+        (name = "rollouts", description = "Progressive rollouts ramping up a fractional split"),
+        (name = "reports", description = "Reports across all flag sets"),
+        (name = "validation", description = "Dry-run validation of flag definition documents"),
+        (name = "admin", description = "Server administration, requires ADMIN_TOKEN")
     ),
     info(
         title = "Flagd UI API",
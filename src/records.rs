@@ -0,0 +1,121 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    metrics::METRICS,
+};
+
+/// Records such as change requests, stored as one JSON file per id in a hidden
+/// directory of the flags directory
+pub struct RecordStore {
+    dir: PathBuf,
+    /// Human readable name of the records, used in error messages
+    kind: &'static str,
+}
+
+impl RecordStore {
+    pub fn new(flags_dir: &str, dir_name: &str, kind: &'static str) -> Self {
+        Self {
+            dir: PathBuf::from(flags_dir).join(dir_name),
+            kind,
+        }
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, id: &str) -> AppResult<T> {
+        let not_found = || AppError::NotFound(format!("{} '{}' not found", self.kind, id));
+
+        // Ids are generated UUIDs, anything else cannot name a stored record
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Err(not_found());
+        }
+
+        let path = self.dir.join(format!("{}.json", id));
+        if !path.exists() {
+            return Err(not_found());
+        }
+
+        let content = fs::read_to_string(&path).map_err(|e| {
+            METRICS.record_storage_error("read");
+            AppError::InternalServerError(format!("Failed to read {} {}: {}", self.kind, id, e))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            AppError::InternalServerError(format!("Failed to parse {} {}: {}", self.kind, id, e))
+        })
+    }
+
+    /// All stored records, in no particular order.
+    ///
+    /// Records that cannot be read or parsed are skipped with a warning, so one
+    /// corrupt file does not hide all others.
+    pub fn list<T: DeserializeOwned>(&self) -> AppResult<Vec<T>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries = fs::read_dir(&self.dir).map_err(|e| {
+            METRICS.record_storage_error("list");
+            AppError::InternalServerError(format!("Failed to list {} records: {}", self.kind, e))
+        })?;
+
+        let mut records = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+            else {
+                continue;
+            };
+            match self.get(id) {
+                Ok(record) => records.push(record),
+                Err(e) => {
+                    tracing::warn!(kind = self.kind, id, error = %e, "Skipping unreadable record");
+                }
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Store a record, replacing the stored version with the same id
+    pub fn save<T: Serialize>(&self, id: &str, record: &T) -> AppResult<()> {
+        let write_error = |e: std::io::Error| {
+            METRICS.record_storage_error("write");
+            AppError::InternalServerError(format!("Failed to write {} {}: {}", self.kind, id, e))
+        };
+
+        fs::create_dir_all(&self.dir).map_err(write_error)?;
+
+        let json = serde_json::to_string_pretty(record).map_err(|e| {
+            AppError::InternalServerError(format!(
+                "Failed to serialize {} {}: {}",
+                self.kind, id, e
+            ))
+        })?;
+
+        // Write to a temporary file first so readers never see a partial record
+        let path = self.dir.join(format!("{}.json", id));
+        let tmp_path = self.dir.join(format!(".{}.json.tmp", id));
+        fs::write(&tmp_path, json).map_err(write_error)?;
+        fs::rename(&tmp_path, &path).map_err(write_error)
+    }
+}
+
+/// A new random record id
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// The current time as a Unix timestamp in seconds
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,334 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Mutex, MutexGuard};
+use utoipa::ToSchema;
+
+use crate::{
+    document::{flag_entries, flag_mut},
+    error::AppResult,
+    records::{new_id, now, RecordStore},
+};
+
+/// Directory in FLAGS_DIR where rollouts are stored, one JSON file each
+const ROLLOUTS_DIR: &str = ".rollouts";
+
+/// Serializes advancing and controlling rollouts
+static ROLLOUT_LOCK: Mutex<()> = Mutex::new(());
+
+/// State of a rollout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RolloutStatus {
+    /// Advancing to the next step when it is due
+    Active,
+    /// Holding the current step until resumed
+    Paused,
+    /// The last step was applied
+    Completed,
+    /// The flag's targeting was restored to what it was before the rollout
+    RolledBack,
+    /// Applying a step failed, see `error`
+    Failed,
+    /// The flag's targeting was changed outside the rollout, which stopped so the
+    /// change is not overwritten
+    Conflict,
+}
+
+impl RolloutStatus {
+    /// Human readable name of the state, used in error messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            RolloutStatus::Active => "active",
+            RolloutStatus::Paused => "paused",
+            RolloutStatus::Completed => "completed",
+            RolloutStatus::RolledBack => "rolled back",
+            RolloutStatus::Failed => "failed",
+            RolloutStatus::Conflict => "stopped by a conflicting change",
+        }
+    }
+}
+
+/// A progressive rollout of a variant, ramping up a `fractional` split step by step
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Rollout {
+    /// Identifier of the rollout
+    #[schema(example = "3f2b8c1d-6e4a-4b9f-a0c2-7d5e1f3a9b8c")]
+    pub id: String,
+    /// Flag set containing the flag
+    #[schema(example = "prod")]
+    pub flag_set: String,
+    /// Key of the flag
+    #[schema(example = "new-checkout")]
+    pub flag: String,
+    /// Variant that is rolled out
+    #[schema(example = "on")]
+    pub variant: String,
+    /// Variant served to everyone outside the rollout percentage
+    #[schema(example = "off")]
+    pub baseline_variant: String,
+    /// Bucketing expression of the `fractional` split, flagd defaults to the flag key
+    /// and targeting key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub bucket_by: Option<Value>,
+    /// Percentages of the variant, applied in order
+    #[schema(example = json!([1, 5, 25, 50, 100]))]
+    pub steps: Vec<u8>,
+    /// Seconds between two steps
+    #[schema(example = 7200)]
+    pub interval_seconds: u64,
+    /// Index of the last applied step
+    pub current_step: usize,
+    pub status: RolloutStatus,
+    /// Unix timestamp in seconds when the next step is applied, while active
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_step_at: Option<u64>,
+    /// Targeting of the flag before the rollout, restored on rollback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub original_targeting: Option<Value>,
+    /// Targeting written by the last applied step, the flag must still have it when
+    /// the rollout changes the targeting again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub applied_targeting: Option<Value>,
+    /// User who started the rollout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Unix timestamp in seconds when the rollout was started
+    pub created_at: u64,
+    /// Unix timestamp in seconds of the last change of the rollout
+    pub updated_at: u64,
+    /// Why applying the last step failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Rollout {
+    /// A new active rollout at its first step, not yet applied to the flag set
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        flag_set: &str,
+        flag: String,
+        variant: String,
+        baseline_variant: String,
+        bucket_by: Option<Value>,
+        steps: Vec<u8>,
+        interval_seconds: u64,
+        author: Option<String>,
+    ) -> Self {
+        let now = now();
+        let mut rollout = Self {
+            id: new_id(),
+            flag_set: flag_set.to_string(),
+            flag,
+            variant,
+            baseline_variant,
+            bucket_by,
+            steps,
+            interval_seconds,
+            current_step: 0,
+            status: RolloutStatus::Active,
+            next_step_at: None,
+            original_targeting: None,
+            applied_targeting: None,
+            author,
+            created_at: now,
+            updated_at: now,
+            error: None,
+        };
+        rollout.schedule_next_step();
+        rollout
+    }
+
+    /// Whether the rollout still controls the flag's targeting
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.status,
+            RolloutStatus::Active
+                | RolloutStatus::Paused
+                | RolloutStatus::Failed
+                | RolloutStatus::Conflict
+        )
+    }
+
+    /// Whether the rollout is active and its next step is due
+    pub fn is_due(&self, now: u64) -> bool {
+        self.status == RolloutStatus::Active && self.next_step_at.is_some_and(|at| at <= now)
+    }
+
+    /// Percentage of the variant at the current step
+    pub fn percentage(&self) -> u8 {
+        self.steps[self.current_step]
+    }
+
+    /// The flag set document with the flag's targeting set to the current step
+    pub fn apply_step(&self, doc: &Value) -> Result<Value, String> {
+        self.set_targeting(doc, Some(self.step_targeting()))
+    }
+
+    /// Targeting of the flag at the current step
+    fn step_targeting(&self) -> Value {
+        let percentage = self.percentage();
+        let mut buckets = Vec::new();
+        if let Some(bucket_by) = &self.bucket_by {
+            buckets.push(bucket_by.clone());
+        }
+        buckets.push(json!([self.variant, percentage]));
+        buckets.push(json!([self.baseline_variant, 100 - percentage]));
+
+        json!({ "fractional": buckets })
+    }
+
+    /// Fail if the flag's targeting in `doc` is no longer the one the last step wrote.
+    ///
+    /// Rollouts stored before the applied targeting was recorded are not checked.
+    pub fn check_targeting(&self, doc: &Value) -> Result<(), String> {
+        let Some(applied) = &self.applied_targeting else {
+            return Ok(());
+        };
+        // A missing flag is reported when the targeting is written
+        let Some((_, flag, _)) = flag_entries(doc)
+            .into_iter()
+            .find(|(key, _, _)| *key == self.flag)
+        else {
+            return Ok(());
+        };
+
+        if flag.get("targeting") != Some(applied) {
+            return Err(format!(
+                "The targeting of flag '{}' was changed outside the rollout",
+                self.flag
+            ));
+        }
+        Ok(())
+    }
+
+    /// The flag set document with the flag's targeting from before the rollout
+    pub fn restore(&self, doc: &Value) -> Result<Value, String> {
+        self.set_targeting(doc, self.original_targeting.clone())
+    }
+
+    fn set_targeting(&self, doc: &Value, targeting: Option<Value>) -> Result<Value, String> {
+        let mut doc = doc.clone();
+        let flag = flag_mut(&mut doc, &self.flag)
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| format!("Flag '{}' does not exist", self.flag))?;
+
+        match targeting {
+            Some(targeting) => flag.insert("targeting".to_string(), targeting),
+            None => flag.remove("targeting"),
+        };
+        Ok(doc)
+    }
+
+    /// Move on to the next step, if there is one
+    pub fn advance(&mut self) {
+        if self.current_step + 1 < self.steps.len() {
+            self.current_step += 1;
+        }
+    }
+
+    /// Record that the current step was applied
+    pub fn step_applied(&mut self) {
+        self.applied_targeting = Some(self.step_targeting());
+        self.error = None;
+        if self.current_step + 1 >= self.steps.len() {
+            self.status = RolloutStatus::Completed;
+            self.next_step_at = None;
+        } else {
+            self.schedule_next_step();
+        }
+        self.updated_at = now();
+    }
+
+    /// Record that applying the current step failed
+    pub fn step_failed(&mut self, error: String) {
+        self.status = RolloutStatus::Failed;
+        self.next_step_at = None;
+        self.error = Some(error);
+        self.updated_at = now();
+    }
+
+    /// Record that the flag's targeting was changed outside the rollout
+    pub fn conflicted(&mut self, error: String) {
+        self.status = RolloutStatus::Conflict;
+        self.next_step_at = None;
+        self.error = Some(error);
+        self.updated_at = now();
+    }
+
+    pub fn pause(&mut self) {
+        self.status = RolloutStatus::Paused;
+        self.next_step_at = None;
+        self.updated_at = now();
+    }
+
+    /// Continue a paused rollout one interval from now, or retry a failed step now.
+    ///
+    /// Resuming after a conflict accepts the changed targeting, which the retried step
+    /// then overwrites.
+    pub fn resume(&mut self) {
+        // The error is kept until the step is applied, so the retry repeats the step
+        let failed = matches!(self.status, RolloutStatus::Failed | RolloutStatus::Conflict);
+        if self.status == RolloutStatus::Conflict {
+            self.applied_targeting = None;
+        }
+        self.status = RolloutStatus::Active;
+        if failed {
+            self.next_step_at = Some(now());
+        } else {
+            self.schedule_next_step();
+        }
+        self.updated_at = now();
+    }
+
+    pub fn rolled_back(&mut self) {
+        self.status = RolloutStatus::RolledBack;
+        self.next_step_at = None;
+        self.error = None;
+        self.updated_at = now();
+    }
+
+    fn schedule_next_step(&mut self) {
+        self.next_step_at = Some(now() + self.interval_seconds);
+    }
+}
+
+/// Rollouts persisted in the flags directory
+pub struct RolloutStore {
+    records: RecordStore,
+}
+
+impl RolloutStore {
+    pub fn new(flags_dir: &str) -> Self {
+        Self {
+            records: RecordStore::new(flags_dir, ROLLOUTS_DIR, "Rollout"),
+        }
+    }
+
+    /// Hold the rollout lock while advancing or controlling rollouts
+    pub fn lock(&self) -> MutexGuard<'static, ()> {
+        // The lock guards no data, a panic while holding it leaves nothing inconsistent
+        ROLLOUT_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn get(&self, id: &str) -> AppResult<Rollout> {
+        self.records.get(id)
+    }
+
+    /// All stored rollouts, oldest first
+    pub fn list(&self) -> AppResult<Vec<Rollout>> {
+        let mut rollouts = self.records.list::<Rollout>()?;
+        rollouts.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(rollouts)
+    }
+
+    /// Store a rollout, replacing the stored version with the same id
+    pub fn save(&self, rollout: &Rollout) -> AppResult<()> {
+        self.records.save(&rollout.id, rollout)
+    }
+}
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Mutex, MutexGuard};
+use utoipa::ToSchema;
+
+use crate::{
+    document::flag_mut,
+    error::AppResult,
+    records::{new_id, now, RecordStore},
+};
+
+/// Directory in FLAGS_DIR where schedules are stored, one JSON file each
+const SCHEDULES_DIR: &str = ".schedules";
+
+/// Serializes running and cancelling schedules, so that a cancelled schedule never runs
+static SCHEDULE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Change applied to a flag set when a schedule is due
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScheduledChange {
+    /// Set the `state` of a flag
+    State {
+        /// Key of the flag
+        #[schema(example = "new-checkout")]
+        flag: String,
+        /// New state of the flag
+        #[schema(example = "ENABLED")]
+        state: String,
+    },
+    /// Set the `defaultVariant` of a flag
+    DefaultVariant {
+        /// Key of the flag
+        #[schema(example = "new-checkout")]
+        flag: String,
+        /// New default variant of the flag
+        #[schema(example = "on")]
+        variant: String,
+    },
+    /// Replace the whole flag set document
+    Document {
+        /// New flag set document, keeping the current `$schema` if it has none
+        #[schema(value_type = Object)]
+        content: Value,
+    },
+}
+
+impl ScheduledChange {
+    /// The flag set document after applying the change to `doc`
+    pub fn apply(&self, doc: &Value) -> Result<Value, String> {
+        match self {
+            ScheduledChange::State { flag, state } => set_flag_field(doc, flag, "state", state),
+            ScheduledChange::DefaultVariant { flag, variant } => {
+                set_flag_field(doc, flag, "defaultVariant", variant)
+            }
+            ScheduledChange::Document { content } => {
+                let mut content = content.clone();
+                let fields = content
+                    .as_object_mut()
+                    .ok_or_else(|| "The scheduled document must be a JSON object".to_string())?;
+                if let (false, Some(schema)) = (fields.contains_key("$schema"), doc.get("$schema"))
+                {
+                    fields.insert("$schema".to_string(), schema.clone());
+                }
+                Ok(content)
+            }
+        }
+    }
+}
+
+fn set_flag_field(doc: &Value, key: &str, field: &str, value: &str) -> Result<Value, String> {
+    let mut doc = doc.clone();
+    let flag = flag_mut(&mut doc, key)
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| format!("Flag '{}' does not exist", key))?;
+
+    flag.insert(field.to_string(), Value::String(value.to_string()));
+    Ok(doc)
+}
+
+/// State of a schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleStatus {
+    /// Waiting for the approval of its change request, the flag set is protected
+    Proposed,
+    Pending,
+    Applied,
+    Failed,
+    Cancelled,
+    /// Its change request was rejected
+    Rejected,
+}
+
+/// A change to a flag set that is applied at a future time
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    /// Identifier of the schedule
+    #[schema(example = "9d3c1a4e-2f7b-4c55-8a0e-6b1f2d3c4e5f")]
+    pub id: String,
+    /// Flag set the change applies to
+    #[schema(example = "prod")]
+    pub flag_set: String,
+    /// Unix timestamp in seconds when the change is applied
+    #[schema(example = 1767236400)]
+    pub run_at: u64,
+    pub change: ScheduledChange,
+    pub status: ScheduleStatus,
+    /// User who created the schedule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Change request approving the change, for schedules of protected flag sets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_request: Option<String>,
+    /// Unix timestamp in seconds when the schedule was created
+    pub created_at: u64,
+    /// Unix timestamp in seconds when the schedule ran or was cancelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    /// Why applying the change failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Schedule {
+    /// A new pending schedule
+    pub fn new(
+        flag_set: &str,
+        run_at: u64,
+        change: ScheduledChange,
+        author: Option<String>,
+    ) -> Self {
+        Self {
+            id: new_id(),
+            flag_set: flag_set.to_string(),
+            run_at,
+            change,
+            status: ScheduleStatus::Pending,
+            author,
+            change_request: None,
+            created_at: now(),
+            finished_at: None,
+            error: None,
+        }
+    }
+
+    /// Whether the schedule is pending and its time has come
+    pub fn is_due(&self, now: u64) -> bool {
+        self.status == ScheduleStatus::Pending && self.run_at <= now
+    }
+
+    /// Record the outcome of the schedule
+    pub fn finish(&mut self, status: ScheduleStatus, error: Option<String>) {
+        self.status = status;
+        self.finished_at = Some(now());
+        self.error = error;
+    }
+}
+
+/// Schedules persisted in the flags directory
+pub struct ScheduleStore {
+    records: RecordStore,
+}
+
+impl ScheduleStore {
+    pub fn new(flags_dir: &str) -> Self {
+        Self {
+            records: RecordStore::new(flags_dir, SCHEDULES_DIR, "Schedule"),
+        }
+    }
+
+    /// Hold the schedule lock while running or cancelling schedules
+    pub fn lock(&self) -> MutexGuard<'static, ()> {
+        // The lock guards no data, a panic while holding it leaves nothing inconsistent
+        SCHEDULE_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn get(&self, id: &str) -> AppResult<Schedule> {
+        self.records.get(id)
+    }
+
+    /// All stored schedules, earliest first
+    pub fn list(&self) -> AppResult<Vec<Schedule>> {
+        let mut schedules = self.records.list::<Schedule>()?;
+        schedules.sort_by(|a, b| a.run_at.cmp(&b.run_at).then(a.id.cmp(&b.id)));
+        Ok(schedules)
+    }
+
+    /// Store a schedule, replacing the stored version with the same id
+    pub fn save(&self, schedule: &Schedule) -> AppResult<()> {
+        self.records.save(&schedule.id, schedule)
+    }
+}
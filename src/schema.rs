@@ -0,0 +1,241 @@
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::error::{AppError, AppResult};
+
+/// A flagd schema version compiled into the binary
+struct BundledSchema {
+    version: &'static str,
+    flags: &'static str,
+    targeting: &'static str,
+}
+
+/// Bundled flagd schemas, oldest first. The last entry is the default version.
+const BUNDLED_SCHEMAS: &[BundledSchema] = &[BundledSchema {
+    version: "v0",
+    flags: include_str!("../schema/flagd-schema.json"),
+    targeting: include_str!("../schema/targeting.json"),
+}];
+
+const SCHEMA_URL_PREFIX: &str = "https://flagd.dev/schema/";
+
+/// `$schema` URL of the flags schema of a version
+pub fn schema_url(version: &str) -> String {
+    format!("{}{}/flags.json", SCHEMA_URL_PREFIX, version)
+}
+
+/// Extract the version from a flagd schema URL like `https://flagd.dev/schema/v0/flags.json`
+pub fn schema_version(url: &str) -> Option<&str> {
+    url.trim_end_matches('#')
+        .strip_prefix(SCHEMA_URL_PREFIX)?
+        .strip_suffix("/flags.json")
+        .filter(|version| !version.is_empty() && !version.contains('/'))
+}
+
+/// Resolves schema references against the known schema documents.
+///
+/// The flags schema has no effective `$id` (draft-07 ignores siblings of a root `$ref`),
+/// so its relative `./targeting.json` reference resolves against `json-schema:///` and
+/// is served from the targeting schema of the version being compiled. `https`
+/// references are only served for the exact URLs of known documents, other relative
+/// and `file` references are resolved on disk next to an override schema file.
+struct LocalSchemaRetriever {
+    targeting: Value,
+    documents: Arc<BTreeMap<String, Value>>,
+    base_dir: Option<PathBuf>,
+}
+
+impl jsonschema::Retrieve for LocalSchemaRetriever {
+    fn retrieve(
+        &self,
+        uri: &jsonschema::Uri<&str>,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let uri_str = uri.as_str();
+
+        if uri_str == "json-schema:///targeting.json" {
+            return Ok(self.targeting.clone());
+        }
+
+        if let Some(document) = self.documents.get(uri_str) {
+            return Ok(document.clone());
+        }
+
+        let candidate = match (&self.base_dir, uri_str.strip_prefix("json-schema:///")) {
+            (Some(base_dir), Some(rel)) => base_dir.join(rel),
+            _ if uri.scheme().as_str() == "file" => PathBuf::from(uri.path().as_str()),
+            _ => return Err(format!("Unsupported schema URI: {uri_str}").into()),
+        };
+
+        let content = fs::read_to_string(&candidate)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Compiled flagd schema validators, one per schema version
+pub struct SchemaRegistry {
+    validators: BTreeMap<String, jsonschema::Validator>,
+    default_version: String,
+    source: String,
+}
+
+impl SchemaRegistry {
+    /// Compile the bundled schemas, optionally overriding one version with a schema
+    /// file on disk.
+    ///
+    /// The override replaces the version named in its `$id` (or the default version if
+    /// it has none). A sibling `targeting.json` overrides the targeting schema as well.
+    pub fn load(override_path: Option<&str>) -> AppResult<Self> {
+        let mut schemas = BTreeMap::new();
+        for bundled in BUNDLED_SCHEMAS {
+            schemas.insert(
+                bundled.version.to_string(),
+                (
+                    parse_schema(bundled.flags, bundled.version)?,
+                    parse_schema(bundled.targeting, bundled.version)?,
+                ),
+            );
+        }
+
+        let default_version = BUNDLED_SCHEMAS
+            .last()
+            .map(|bundled| bundled.version.to_string())
+            .unwrap_or_default();
+
+        let mut base_dir = None;
+        let mut source = "bundled".to_string();
+
+        if let Some(path) = override_path {
+            let flags = read_schema_file(Path::new(path))?;
+            let version = flags
+                .get("$id")
+                .and_then(Value::as_str)
+                .and_then(schema_version)
+                .unwrap_or(&default_version)
+                .to_string();
+
+            let dir = Path::new(path)
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_else(|| PathBuf::from("."));
+
+            let targeting_path = dir.join("targeting.json");
+            let targeting = if targeting_path.exists() {
+                read_schema_file(&targeting_path)?
+            } else {
+                schemas
+                    .get(&version)
+                    .map(|(_, targeting)| targeting.clone())
+                    .ok_or_else(|| {
+                        AppError::InternalServerError(format!(
+                            "Schema {} declares version '{}' which is not bundled and has no sibling targeting.json",
+                            path, version
+                        ))
+                    })?
+            };
+
+            source = format!("{} ({})", path, version);
+            schemas.insert(version, (flags, targeting));
+            base_dir = Some(dir);
+        }
+
+        let documents = schemas
+            .iter()
+            .flat_map(|(version, (flags, targeting))| {
+                [
+                    (schema_url(version), flags.clone()),
+                    (
+                        format!("{}{}/targeting.json", SCHEMA_URL_PREFIX, version),
+                        targeting.clone(),
+                    ),
+                ]
+            })
+            .collect::<BTreeMap<_, _>>();
+        let documents = Arc::new(documents);
+
+        let mut validators = BTreeMap::new();
+        for (version, (flags, targeting)) in &schemas {
+            // Compile the JSON schema into a validator
+            let validator = jsonschema::options()
+                .with_retriever(LocalSchemaRetriever {
+                    targeting: targeting.clone(),
+                    documents: documents.clone(),
+                    base_dir: base_dir.clone(),
+                })
+                .build(flags)
+                .map_err(|e| {
+                    AppError::InternalServerError(format!(
+                        "Invalid schema for version {}: {}",
+                        version, e
+                    ))
+                })?;
+            validators.insert(version.clone(), validator);
+        }
+
+        Ok(Self {
+            validators,
+            default_version,
+            source,
+        })
+    }
+
+    /// Select the validator for the schema version a document declares in `$schema`.
+    ///
+    /// Documents without `$schema` are validated against the default version.
+    pub fn validator_for(&self, doc: &Value) -> AppResult<(&str, &jsonschema::Validator)> {
+        let version = match doc.get("$schema") {
+            None => self.default_version.as_str(),
+            Some(url) => url
+                .as_str()
+                .and_then(schema_version)
+                .filter(|version| self.validators.contains_key(*version))
+                .ok_or_else(|| {
+                    AppError::ValidationFailed(format!(
+                        "Unsupported $schema {}, supported versions: {}",
+                        url,
+                        self.versions().collect::<Vec<_>>().join(", ")
+                    ))
+                })?,
+        };
+
+        let (version, validator) = self
+            .validators
+            .get_key_value(version)
+            .ok_or_else(|| AppError::InternalServerError("No schema loaded".to_string()))?;
+
+        Ok((version.as_str(), validator))
+    }
+
+    /// `$schema` URL of the default version, used for new flag sets
+    pub fn default_schema_url(&self) -> String {
+        schema_url(&self.default_version)
+    }
+
+    /// All supported schema versions
+    pub fn versions(&self) -> impl Iterator<Item = &str> {
+        self.validators.keys().map(String::as_str)
+    }
+
+    /// Human readable description of where the schemas were loaded from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+fn parse_schema(content: &str, version: &str) -> AppResult<Value> {
+    serde_json::from_str(content).map_err(|e| {
+        AppError::InternalServerError(format!("Failed to parse bundled schema {}: {}", version, e))
+    })
+}
+
+fn read_schema_file(path: &Path) -> AppResult<Value> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to read schema file: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to parse schema: {}", e)))
+}
@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+use utoipa::ToSchema;
+
+use crate::document::{escape_pointer, flag_entries};
+
+/// Part of a flag a search term matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchField {
+    Key,
+    VariantName,
+    VariantValue,
+    Metadata,
+    Targeting,
+}
+
+/// Type of a flag, derived from the values of its variants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagType {
+    Boolean,
+    String,
+    Number,
+    Object,
+}
+
+/// A value of a flag that matched the query
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchMatch {
+    /// JSON pointer to the matching key or value within the flag set
+    #[schema(example = "/flags/new-checkout/targeting/if/0/ends_with/1")]
+    pub pointer: String,
+    pub field: SearchField,
+    /// The matching text
+    #[schema(example = "@acme.com")]
+    pub text: String,
+}
+
+/// A flag matching all terms of the query
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    /// Flag set containing the flag
+    #[schema(example = "prod")]
+    pub flag_set: String,
+    /// Key of the flag
+    #[schema(example = "new-checkout")]
+    pub flag: String,
+    #[schema(example = "ENABLED")]
+    pub state: Option<String>,
+    #[serde(rename = "type")]
+    pub flag_type: Option<FlagType>,
+    /// Where the terms matched
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Filters of a search besides the query terms
+#[derive(Debug, Default)]
+pub struct SearchFilter {
+    pub state: Option<String>,
+    pub flag_type: Option<FlagType>,
+}
+
+/// Searchable text of a flag
+#[derive(Debug)]
+struct IndexedFlag {
+    key: String,
+    state: Option<String>,
+    flag_type: Option<FlagType>,
+    /// Pointer, field, original text and lowercase text of every searchable entry
+    entries: Vec<(String, SearchField, String, String)>,
+}
+
+#[derive(Debug)]
+struct IndexedFlagSet {
+    flags: Vec<IndexedFlag>,
+}
+
+/// In-memory index of the searchable text of all flag sets.
+///
+/// Every write through the API updates the entry of its flag set, so searches never
+/// touch the files. Edits of the files on disk are picked up when the index is
+/// rebuilt, at startup and on a configuration reload.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    flag_sets: Arc<RwLock<HashMap<String, Arc<IndexedFlagSet>>>>,
+}
+
+impl SearchIndex {
+    /// Replace the index with the flag sets `names` in `flags_dir`.
+    ///
+    /// Files that cannot be read or parsed are left out of the index.
+    pub fn rebuild(&self, flags_dir: &Path, names: &[String]) {
+        let mut rebuilt = HashMap::new();
+        for name in names {
+            let path = flags_dir.join(format!("{}.flagd.json", name));
+            let Some(doc) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            else {
+                tracing::warn!(flag_set = %name, "Skipping unreadable flag set in search index");
+                continue;
+            };
+            rebuilt.insert(name.clone(), Arc::new(index_flag_set(&doc)));
+        }
+
+        *self.write() = rebuilt;
+    }
+
+    /// Index the document just written to a flag set
+    pub fn update(&self, name: &str, doc: &Value) {
+        let indexed = Arc::new(index_flag_set(doc));
+        self.write().insert(name.to_string(), indexed);
+    }
+
+    /// Drop a deleted flag set from the index
+    pub fn remove(&self, name: &str) {
+        self.write().remove(name);
+    }
+
+    /// Move the entry of a renamed flag set to its new name
+    pub fn rename(&self, name: &str, new_name: &str) {
+        let mut flag_sets = self.write();
+        if let Some(indexed) = flag_sets.remove(name) {
+            flag_sets.insert(new_name.to_string(), indexed);
+        }
+    }
+
+    /// Flags matching all whitespace separated terms of `query`, case-insensitively,
+    /// ordered by flag set and flag
+    pub fn search(&self, query: &str, filter: &SearchFilter) -> Vec<SearchResult> {
+        let terms = query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+
+        let flag_sets = self.read();
+        let mut names = flag_sets.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut results = Vec::new();
+        for name in names {
+            for flag in &flag_sets[name].flags {
+                if filter
+                    .state
+                    .as_ref()
+                    .is_some_and(|state| flag.state.as_ref() != Some(state))
+                    || filter
+                        .flag_type
+                        .is_some_and(|flag_type| flag.flag_type != Some(flag_type))
+                {
+                    continue;
+                }
+
+                let matching = flag
+                    .entries
+                    .iter()
+                    .filter(|(_, _, _, lower)| terms.iter().any(|term| lower.contains(term)))
+                    .collect::<Vec<_>>();
+                let all_terms_match = terms
+                    .iter()
+                    .all(|term| matching.iter().any(|(_, _, _, lower)| lower.contains(term)));
+                if matching.is_empty() || !all_terms_match {
+                    continue;
+                }
+
+                results.push(SearchResult {
+                    flag_set: name.clone(),
+                    flag: flag.key.clone(),
+                    state: flag.state.clone(),
+                    flag_type: flag.flag_type,
+                    matches: matching
+                        .into_iter()
+                        .map(|(pointer, field, text, _)| SearchMatch {
+                            pointer: pointer.clone(),
+                            field: *field,
+                            text: text.clone(),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        results
+    }
+
+    // Entries are replaced whole, a panic while holding the lock leaves them consistent
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Arc<IndexedFlagSet>>> {
+        self.flag_sets
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Arc<IndexedFlagSet>>> {
+        self.flag_sets
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn index_flag_set(doc: &Value) -> IndexedFlagSet {
+    let flags = flag_entries(doc)
+        .into_iter()
+        .map(|(key, flag, pointer)| {
+            let mut entries = Vec::new();
+            push_entry(&mut entries, pointer.clone(), SearchField::Key, key.clone());
+
+            let variants = flag.get("variants").and_then(Value::as_object);
+            for (name, value) in variants.into_iter().flatten() {
+                let variant_pointer = format!("{}/variants/{}", pointer, escape_pointer(name));
+                push_entry(
+                    &mut entries,
+                    variant_pointer.clone(),
+                    SearchField::VariantName,
+                    name.clone(),
+                );
+                collect_text(
+                    value,
+                    &variant_pointer,
+                    SearchField::VariantValue,
+                    &mut entries,
+                );
+            }
+            if let Some(metadata) = flag.get("metadata") {
+                collect_text(
+                    metadata,
+                    &format!("{}/metadata", pointer),
+                    SearchField::Metadata,
+                    &mut entries,
+                );
+            }
+            if let Some(targeting) = flag.get("targeting") {
+                collect_text(
+                    targeting,
+                    &format!("{}/targeting", pointer),
+                    SearchField::Targeting,
+                    &mut entries,
+                );
+            }
+
+            IndexedFlag {
+                key,
+                state: flag
+                    .get("state")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                flag_type: variants.and_then(|variants| flag_type(variants.values())),
+                entries,
+            }
+        })
+        .collect();
+
+    IndexedFlagSet { flags }
+}
+
+/// Index the scalar values below `value`, and for targeting also the object keys,
+/// which hold the operator names and variant names of rules
+fn collect_text(
+    value: &Value,
+    pointer: &str,
+    field: SearchField,
+    entries: &mut Vec<(String, SearchField, String, String)>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let child_pointer = format!("{}/{}", pointer, escape_pointer(key));
+                if field == SearchField::Targeting {
+                    push_entry(entries, child_pointer.clone(), field, key.clone());
+                }
+                collect_text(value, &child_pointer, field, entries);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect_text(item, &format!("{}/{}", pointer, index), field, entries);
+            }
+        }
+        Value::String(text) => push_entry(entries, pointer.to_string(), field, text.clone()),
+        Value::Number(number) => {
+            push_entry(entries, pointer.to_string(), field, number.to_string())
+        }
+        Value::Bool(value) => push_entry(entries, pointer.to_string(), field, value.to_string()),
+        Value::Null => {}
+    }
+}
+
+fn push_entry(
+    entries: &mut Vec<(String, SearchField, String, String)>,
+    pointer: String,
+    field: SearchField,
+    text: String,
+) {
+    let lower = text.to_lowercase();
+    entries.push((pointer, field, text, lower));
+}
+
+/// The common type of the variant values, if they have one
+fn flag_type<'a>(values: impl Iterator<Item = &'a Value>) -> Option<FlagType> {
+    let types = values
+        .map(|value| match value {
+            Value::Bool(_) => Some(FlagType::Boolean),
+            Value::String(_) => Some(FlagType::String),
+            Value::Number(_) => Some(FlagType::Number),
+            Value::Object(_) | Value::Array(_) => Some(FlagType::Object),
+            Value::Null => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    match types.len() {
+        1 => types.into_iter().next().flatten(),
+        _ => None,
+    }
+}
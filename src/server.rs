@@ -0,0 +1,258 @@
+use axum::{extract::Request, Router};
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{
+        conn::auto::Builder,
+        graceful::{GracefulShutdown, Watcher},
+    },
+};
+use std::{fmt, io, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+};
+use tower::Service;
+
+use crate::{
+    actor::ClientAddr, config::ServerConfig, handlers::admin::VerifiedClientCertificate,
+    tls::TlsState,
+};
+
+/// How long a client may take to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// First delay before accepting again after an accept error, doubled up to
+/// [`MAX_ACCEPT_BACKOFF`] while errors persist
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// A connected client stream, TCP or Unix domain socket
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Socket the server accepts connections on
+pub enum Listener {
+    Tcp(TcpListener, SocketAddr),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, std::path::PathBuf),
+}
+
+impl Listener {
+    /// Bind the Unix socket if one is configured, the TCP address otherwise
+    pub async fn bind(config: &ServerConfig) -> io::Result<Self> {
+        #[cfg(unix)]
+        if let Some(path) = &config.unix_socket {
+            use std::os::unix::fs::FileTypeExt;
+
+            let path = std::path::PathBuf::from(path);
+            // Remove a socket left behind by a previous run, but never other files
+            if std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_socket()) {
+                std::fs::remove_file(&path)?;
+            }
+
+            let listener = tokio::net::UnixListener::bind(&path)?;
+            return Ok(Listener::Unix(listener, path));
+        }
+
+        let addr = config.listen_addr();
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr().unwrap_or(addr);
+        Ok(Listener::Tcp(listener, addr))
+    }
+
+    async fn accept(&self) -> io::Result<(Box<dyn Stream>, ClientAddr)> {
+        match self {
+            Listener::Tcp(listener, _) => {
+                let (stream, remote_addr) = listener.accept().await?;
+                Ok((Box::new(stream), ClientAddr::Tcp(remote_addr)))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Box::new(stream), ClientAddr::Unix))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Listener::Tcp(_, addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Serve the application until SIGTERM or SIGINT, then stop accepting connections
+/// and wait up to `shutdown_timeout` for in-flight requests to finish.
+///
+/// Requests carry the [`ClientAddr`] of their connection. With TLS enabled, requests
+/// on connections with a verified client certificate also carry the
+/// [`VerifiedClientCertificate`] extension.
+pub async fn serve(
+    listener: Listener,
+    app: Router,
+    tls: Option<Arc<TlsState>>,
+    shutdown_timeout: Duration,
+) {
+    let graceful = GracefulShutdown::new();
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    let mut accept_backoff = MIN_ACCEPT_BACKOFF;
+
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+
+        let (stream, client) = match accepted {
+            Ok(connection) => {
+                accept_backoff = MIN_ACCEPT_BACKOFF;
+                connection
+            }
+            Err(error) => {
+                // Errors like EMFILE persist until connections close, so retrying
+                // right away would only spin
+                tracing::warn!(
+                    error = %error,
+                    retry_in_ms = accept_backoff.as_millis() as u64,
+                    "Failed to accept connection"
+                );
+                tokio::select! {
+                    _ = tokio::time::sleep(accept_backoff) => {}
+                    _ = &mut shutdown => break,
+                }
+                accept_backoff = (accept_backoff * 2).min(MAX_ACCEPT_BACKOFF);
+                continue;
+            }
+        };
+
+        tokio::spawn(handle_connection(
+            stream,
+            client,
+            app.clone(),
+            tls.clone(),
+            graceful.watcher(),
+        ));
+    }
+
+    // Stop accepting new connections
+    drop(listener);
+
+    tracing::info!(
+        "Shutting down, waiting up to {}s for {} open connections",
+        shutdown_timeout.as_secs(),
+        graceful.count()
+    );
+
+    match tokio::time::timeout(shutdown_timeout, graceful.shutdown()).await {
+        Ok(()) => tracing::info!("All connections closed"),
+        Err(_) => tracing::warn!("Shutdown timeout elapsed, closing remaining connections"),
+    }
+}
+
+/// Complete the TLS handshake if enabled and serve HTTP on the connection
+async fn handle_connection(
+    stream: Box<dyn Stream>,
+    client: ClientAddr,
+    app: Router,
+    tls: Option<Arc<TlsState>>,
+    watcher: Watcher,
+) {
+    let Some(tls) = tls else {
+        serve_connection(stream, client, false, app, watcher).await;
+        return;
+    };
+
+    let stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.acceptor().accept(stream)).await
+    {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(error)) => {
+            tracing::debug!(?client, error = %error, "TLS handshake failed");
+            return;
+        }
+        Err(_) => {
+            tracing::debug!(?client, "TLS handshake timed out");
+            return;
+        }
+    };
+
+    let verified = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .is_some_and(|certs| !certs.is_empty());
+
+    serve_connection(stream, client, verified, app, watcher).await;
+}
+
+async fn serve_connection<S: Stream + 'static>(
+    stream: S,
+    client: ClientAddr,
+    verified_client: bool,
+    app: Router,
+    watcher: Watcher,
+) {
+    let service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+        request.extensions_mut().insert(client);
+        if verified_client {
+            request.extensions_mut().insert(VerifiedClientCertificate);
+        }
+        app.clone().call(request)
+    });
+
+    let builder = Builder::new(TokioExecutor::new());
+    let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+
+    if let Err(error) = watcher.watch(connection.into_owned()).await {
+        tracing::debug!(?client, error = %error, "Connection closed with error");
+    }
+}
+
+/// Resolve once the process receives SIGTERM or SIGINT
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %error, "Failed to install SIGINT handler");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(error) => {
+                tracing::error!(error = %error, "Failed to install SIGTERM handler");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
@@ -0,0 +1,247 @@
+use axum::{
+    extract::MatchedPath,
+    http::{HeaderMap, Request, Response},
+};
+use opentelemetry::{global, propagation::Extractor, trace::TracerProvider as _};
+use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
+use opentelemetry_otlp::{LogExporter, Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    logs::SdkLoggerProvider, propagation::TraceContextPropagator, trace::SdkTracerProvider,
+    Resource,
+};
+use std::{env, time::Duration};
+use tower_http::trace::{DefaultOnResponse, OnResponse};
+use tracing::{field::Empty, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    filter::filter_fn, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
+};
+
+use crate::{actor::ACTOR_HEADER, config::LogFormat, middleware::X_REQUEST_ID};
+
+/// OpenTelemetry providers installed by [`init`], flushed on shutdown
+#[derive(Default)]
+pub struct Telemetry {
+    tracer_provider: Option<SdkTracerProvider>,
+    logger_provider: Option<SdkLoggerProvider>,
+}
+
+impl Telemetry {
+    /// Export the remaining spans and logs and stop the exporters
+    pub fn shutdown(self) {
+        if let Some(provider) = self.tracer_provider {
+            if let Err(error) = provider.shutdown() {
+                eprintln!("Failed to shut down trace export: {}", error);
+            }
+        }
+        if let Some(provider) = self.logger_provider {
+            if let Err(error) = provider.shutdown() {
+                eprintln!("Failed to shut down log export: {}", error);
+            }
+        }
+    }
+}
+
+/// Install the global tracing subscriber.
+///
+/// Logs are always written to stdout in the configured format. JSON lines carry the
+/// fields of the current request span (request id, route, flag set, actor). Traces
+/// and logs are additionally exported via OTLP over HTTP when
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` (or the signal specific
+/// `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` / `OTEL_EXPORTER_OTLP_LOGS_ENDPOINT`) is set.
+/// The exporters read the other standard `OTEL_*` variables; `OTEL_TRACES_EXPORTER`
+/// and `OTEL_LOGS_EXPORTER` set to `none` disable a signal.
+pub fn init(log_format: LogFormat) -> Telemetry {
+    let mut telemetry = Telemetry::default();
+    let mut errors = Vec::new();
+
+    if otlp_enabled("TRACES") {
+        match tracer_provider() {
+            Ok(provider) => telemetry.tracer_provider = Some(provider),
+            Err(error) => errors.push(format!("Failed to set up OTLP trace export: {}", error)),
+        }
+    }
+    if otlp_enabled("LOGS") {
+        match logger_provider() {
+            Ok(provider) => telemetry.logger_provider = Some(provider),
+            Err(error) => errors.push(format!("Failed to set up OTLP log export: {}", error)),
+        }
+    }
+
+    // Honor W3C `traceparent` headers of incoming requests
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let trace_layer = telemetry.tracer_provider.as_ref().map(|provider| {
+        global::set_tracer_provider(provider.clone());
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("flagd-ui"))
+    });
+
+    // The exporters log through `tracing` themselves, keep them out of the export
+    let log_layer = telemetry.logger_provider.as_ref().map(|provider| {
+        OpenTelemetryTracingBridge::new(provider).with_filter(filter_fn(|metadata| {
+            !["opentelemetry", "reqwest", "hyper"]
+                .iter()
+                .any(|target| metadata.target().starts_with(target))
+        }))
+    });
+
+    let (text_layer, json_layer) = match log_format {
+        LogFormat::Text => (Some(tracing_subscriber::fmt::layer()), None),
+        LogFormat::Json => (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(false),
+            ),
+        ),
+    };
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .with(text_layer)
+        .with(json_layer)
+        .with(trace_layer)
+        .with(log_layer)
+        .init();
+
+    for error in errors {
+        tracing::error!("{}", error);
+    }
+    if telemetry.tracer_provider.is_some() || telemetry.logger_provider.is_some() {
+        tracing::info!(
+            traces = telemetry.tracer_provider.is_some(),
+            logs = telemetry.logger_provider.is_some(),
+            "OpenTelemetry export enabled"
+        );
+    }
+
+    telemetry
+}
+
+/// Whether a signal (`TRACES` or `LOGS`) has an OTLP endpoint and is not disabled
+fn otlp_enabled(signal: &str) -> bool {
+    let is_set = |name: &str| env::var(name).is_ok_and(|value| !value.is_empty());
+
+    let disabled = env::var("OTEL_SDK_DISABLED").is_ok_and(|value| value == "true")
+        || env::var(format!("OTEL_{}_EXPORTER", signal)).is_ok_and(|value| value == "none");
+
+    !disabled
+        && (is_set("OTEL_EXPORTER_OTLP_ENDPOINT")
+            || is_set(&format!("OTEL_EXPORTER_OTLP_{}_ENDPOINT", signal)))
+}
+
+/// OTLP transport from `OTEL_EXPORTER_OTLP_PROTOCOL`, `http/protobuf` by default
+fn protocol() -> Result<Protocol, String> {
+    match env::var("OTEL_EXPORTER_OTLP_PROTOCOL").as_deref() {
+        Err(_) | Ok("") | Ok("http/protobuf") => Ok(Protocol::HttpBinary),
+        Ok("http/json") => Ok(Protocol::HttpJson),
+        Ok(other) => Err(format!(
+            "unsupported OTEL_EXPORTER_OTLP_PROTOCOL '{}', expected http/protobuf or http/json",
+            other
+        )),
+    }
+}
+
+/// Resource from `OTEL_SERVICE_NAME` and `OTEL_RESOURCE_ATTRIBUTES`, named `flagd-ui`
+/// unless configured otherwise
+fn resource() -> Resource {
+    let builder = Resource::builder();
+    let builder = if env::var("OTEL_SERVICE_NAME").is_ok() {
+        builder
+    } else {
+        builder.with_service_name("flagd-ui")
+    };
+
+    builder
+        .with_attribute(opentelemetry::KeyValue::new(
+            "service.version",
+            env!("CARGO_PKG_VERSION"),
+        ))
+        .build()
+}
+
+fn tracer_provider() -> Result<SdkTracerProvider, String> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(protocol()?)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource())
+        .build())
+}
+
+fn logger_provider() -> Result<SdkLoggerProvider, String> {
+    let exporter = LogExporter::builder()
+        .with_http()
+        .with_protocol(protocol()?)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    Ok(SdkLoggerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource())
+        .build())
+}
+
+/// Reads trace context from request headers
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Create the server span of a request, continuing the trace of its `traceparent` header.
+///
+/// Handlers record the `flag_set` they work on in this span.
+pub fn make_request_span<B>(request: &Request<B>) -> Span {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or("static");
+
+    let span = tracing::info_span!(
+        "request",
+        otel.name = format!("{} {}", request.method(), route),
+        otel.kind = "server",
+        http.request.method = %request.method(),
+        http.route = route,
+        url.path = request.uri().path(),
+        http.response.status_code = Empty,
+        request_id = header(X_REQUEST_ID.as_str()),
+        flag_set = Empty,
+        actor = header(ACTOR_HEADER),
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    // Only fails if trace export is disabled
+    let _ = span.set_parent(parent);
+
+    span
+}
+
+/// Record the response status on the request span and log the response
+pub fn on_response<B>(response: &Response<B>, latency: Duration, span: &Span) {
+    span.record("http.response.status_code", response.status().as_u16());
+    DefaultOnResponse::default().on_response(response, latency, span);
+}
@@ -0,0 +1,171 @@
+use arc_swap::ArcSwap;
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore,
+};
+use std::{
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::{ClientAuth, ServerConfig};
+
+/// How often the certificate files are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Certificate, key and client CA files of the TLS listener
+#[derive(Debug, Clone)]
+struct TlsFiles {
+    cert: PathBuf,
+    key: PathBuf,
+    client_ca: Option<PathBuf>,
+    client_auth: ClientAuth,
+}
+
+impl TlsFiles {
+    fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        [&self.cert, &self.key].into_iter().chain(&self.client_ca)
+    }
+
+    /// Modification times of all files, used to detect replaced certificates
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        self.paths()
+            .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+/// rustls configuration of the listener, swapped when the certificate files change
+pub struct TlsState {
+    files: TlsFiles,
+    server_config: ArcSwap<rustls::ServerConfig>,
+}
+
+impl TlsState {
+    /// Load the TLS files named in the configuration, `None` if TLS is disabled
+    pub fn load(config: &ServerConfig) -> Result<Option<Arc<Self>>, String> {
+        let (Some(cert), Some(key)) = (&config.tls_cert_file, &config.tls_key_file) else {
+            return Ok(None);
+        };
+
+        let files = TlsFiles {
+            cert: PathBuf::from(cert),
+            key: PathBuf::from(key),
+            client_ca: config.tls_client_ca_file.as_ref().map(PathBuf::from),
+            client_auth: config.tls_client_auth,
+        };
+        let server_config = build_server_config(&files)?;
+
+        Ok(Some(Arc::new(Self {
+            files,
+            server_config: ArcSwap::from_pointee(server_config),
+        })))
+    }
+
+    /// Reload the certificates whenever one of the files changes.
+    ///
+    /// Files are polled rather than watched so that atomically replaced files and
+    /// symlink swaps (as done for Kubernetes secrets) are picked up as well. If the
+    /// new files are invalid, the previous certificates stay active.
+    pub async fn watch(self: Arc<Self>) -> Result<(), String> {
+        let mut modified = self.files.modified();
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            let current = self.files.modified();
+            if current == modified {
+                continue;
+            }
+            modified = current;
+
+            match build_server_config(&self.files) {
+                Ok(server_config) => {
+                    self.server_config.store(Arc::new(server_config));
+                    tracing::info!("TLS certificates reloaded");
+                }
+                Err(error) => {
+                    tracing::error!(error = %error, "Failed to reload TLS certificates, keeping the previous ones");
+                }
+            }
+        }
+    }
+
+    /// Acceptor using the current certificates
+    pub fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(self.server_config.load_full())
+    }
+}
+
+/// Build the rustls configuration from the certificate files
+fn build_server_config(files: &TlsFiles) -> Result<rustls::ServerConfig, String> {
+    let certs = read_certificates(&files.cert)?;
+    let key = read_private_key(&files.key)?;
+
+    let builder = rustls::ServerConfig::builder();
+    let builder = match &files.client_ca {
+        Some(client_ca) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certificates(client_ca)? {
+                roots.add(cert).map_err(|e| {
+                    format!("Invalid CA certificate in {}: {}", client_ca.display(), e)
+                })?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots));
+            let verifier = match files.client_auth {
+                ClientAuth::Required => verifier,
+                ClientAuth::Optional => verifier.allow_unauthenticated(),
+            };
+            let verifier = verifier
+                .build()
+                .map_err(|e| format!("Invalid client CA {}: {}", client_ca.display(), e))?;
+
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder.with_single_cert(certs, key).map_err(|e| {
+        format!(
+            "Invalid TLS certificate {} or key {}: {}",
+            files.cert.display(),
+            files.key.display(),
+            e
+        )
+    })?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(server_config)
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file =
+        fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", path.display()));
+    }
+
+    Ok(certs)
+}
+
+fn read_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    let file =
+        fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+        .ok_or_else(|| format!("No private key found in {}", path.display()))
+}